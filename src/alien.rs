@@ -1,9 +1,8 @@
-use crate::array_sprite::ArraySprite;
+use crate::array_sprite::{array_sprite, ArraySprite};
 use crate::canvas;
 use crate::dot_map::DotMap;
 use crate::player::Player;
-use macroquad::audio::Sound;
-use macroquad::audio::*;
+use crate::sound::{Se, SoundQueue};
 use macroquad::prelude::*;
 
 enum BulletType {
@@ -46,7 +45,7 @@ impl Bullet {
         self.flying_cnt = 0;
         self.speed = speed;
     }
-    fn update(&mut self, dot_map: &mut DotMap, player: &mut Player) {
+    fn update(&mut self, dot_map: &mut DotMap, players: &mut [&mut Player], se: &mut SoundQueue) {
         if self.live {
             // 弾が飛翔中
             self.flying_cnt += 1;
@@ -88,20 +87,22 @@ impl Bullet {
             if canvas::GAME_HEIGHT - 8 * 3 < self.pos.y + 8
                 && self.pos.y < canvas::GAME_HEIGHT - 8 * 2
             {
-                // プレイヤーが爆発中でなければ
-                if player.explosion_cnt == None {
-                    // プレイヤーを破壊する
-                    player.remove(dot_map);
+                for player in players.iter_mut() {
+                    // 弾と横方向に重なっていて、爆発中でないプレイヤーを破壊する
+                    let hit =
+                        player.pos.x < self.pos.x + 3 && self.pos.x < player.pos.x + player.width();
+                    if hit && player.explosion_cnt.is_none() {
+                        player.remove(dot_map, se);
+                    }
                 }
             }
             self.pos.x -= 3;
             self.pos.y += 3;
             self.create_explosion_effect(dot_map);
-            return;
         }
     }
     fn draw(&self, dot_map: &mut DotMap) {
-        if !self.live || self.explosion_cnt != None {
+        if !self.live || self.explosion_cnt.is_some() {
             return;
         }
         self.array_shifted_sprite(dot_map);
@@ -162,7 +163,7 @@ impl ArraySprite for Bullet {
     }
     fn sprite(&self) -> &[u8] {
         // 描画するのが弾か爆発エフェクトか
-        if self.explosion_cnt == None {
+        if self.explosion_cnt.is_none() {
             &self.sprite
         } else {
             &self.explosion_sprite
@@ -205,13 +206,20 @@ pub struct BulletManage {
     speed: i32,
     // 射撃禁止状態の残りカウント
     ban_fire_cnt: Option<i32>,
+    // 次にrolling shotで狙うプレイヤー(協力プレイでは交互に狙う)
+    target: usize,
+    // 真なら発射列表の代わりに、対戦プレイの2人目が指示した列から撃つ
+    pub commanded: bool,
+    // 2人目が指示した、次にplunger shotかsquiggly shotを撃つ列
+    order: Option<usize>,
 }
 impl BulletManage {
     pub fn new(explosion_sprite: Vec<u8>) -> Self {
-        let mut bullets = Vec::new();
-        bullets.push(Bullet::new(BulletType::Rolling, explosion_sprite.clone()));
-        bullets.push(Bullet::new(BulletType::Plunger, explosion_sprite.clone()));
-        bullets.push(Bullet::new(BulletType::Squiggly, explosion_sprite.clone()));
+        let bullets = vec![
+            Bullet::new(BulletType::Rolling, explosion_sprite.clone()),
+            Bullet::new(BulletType::Plunger, explosion_sprite.clone()),
+            Bullet::new(BulletType::Squiggly, explosion_sprite),
+        ];
 
         BulletManage {
             bullets,
@@ -232,16 +240,39 @@ impl BulletManage {
             reload_cnt: 48, // 0x30 * 1.5
             speed: 1,
             ban_fire_cnt: None,
+            target: 0,
+            commanded: false,
+            order: None,
         }
     }
+    // 画面上にある弾の左上位置の一覧
+    pub fn live_bullet_pos(&self) -> Vec<IVec2> {
+        self.bullets
+            .iter()
+            .filter(|b| b.live)
+            .map(|b| b.pos)
+            .collect()
+    }
+    // 次に撃つ列を指示する(前の指示がまだ撃たれていなければ上書きする)
+    pub fn order(&mut self, column: usize) {
+        self.order = Some(column);
+    }
     pub fn reset(&mut self) {
         // 開始から一定時間は発射しない
         self.ban_fire_cnt = Some(120);
+        self.order = None;
         for b in self.bullets.iter_mut() {
             b.live = false;
         }
     }
-    pub fn update(&mut self, dot_map: &mut DotMap, player: &mut Player, alien: &Alien, score: i32) {
+    pub fn update(
+        &mut self,
+        dot_map: &mut DotMap,
+        players: &mut [&mut Player],
+        alien: &Alien,
+        score: i32,
+        se: &mut SoundQueue,
+    ) {
         // 獲得点数に応じて発射頻度を変える
         self.set_reload_cnt(score);
         if let Some(cnt) = self.ban_fire_cnt {
@@ -251,64 +282,99 @@ impl BulletManage {
                 self.ban_fire_cnt = Some(cnt - 1);
             }
         } else {
-            // プレイヤーが爆発していたら
-            if let Some(cnt) = player.explosion_cnt {
+            // どれかのプレイヤーが爆発していたら
+            if let Some(cnt) = players.iter().find_map(|player| player.explosion_cnt) {
                 // プレイヤーの爆発が終わってから一定時間は発射しない
                 self.ban_fire_cnt = Some(cnt + 80);
-            } else if alien.explosion.effect_cnt == None {
+            } else if alien.explosion.effect_cnt.is_none() {
                 // プレイヤーとエイリアンのどちらも爆発中でない場合に発射処理を行う
-                self.which_fire(player, alien);
+                self.which_fire(players, alien);
             }
         }
-        for i in 0..self.bullets.len() {
-            self.bullets[i].update(dot_map, player);
+        for bullet in self.bullets.iter_mut() {
+            bullet.update(dot_map, players, se);
         }
         self.draw(dot_map);
     }
     fn draw(&self, dot_map: &mut DotMap) {
-        for i in 0..self.bullets.len() {
-            self.bullets[i].draw(dot_map);
+        for bullet in self.bullets.iter() {
+            bullet.draw(dot_map);
         }
     }
     // どのエイリアンがどの種類の弾を撃つか決める
-    fn which_fire(&mut self, player: &Player, alien: &Alien) {
-        let seed = (player.pos.x + alien.ref_alien_pos.x).abs() as usize % 3;
+    fn which_fire(&mut self, players: &[&mut Player], alien: &Alien) {
+        let Some(target) = players.get(self.target % players.len().max(1)) else {
+            return;
+        };
+        let target_x = target.pos.x;
+        let seed = (target_x + alien.ref_alien_pos.x).unsigned_abs() as usize % 3;
         // 自身が画面上に無く、かつ他2種の弾が発射してから一定時間経過した後
         // rolling shot(自機を狙う弾)
-        if seed == 0 && !self.bullets[seed].live && self.bullets[seed].explosion_cnt == None {
+        if seed == 0 && !self.bullets[seed].live && self.bullets[seed].explosion_cnt.is_none() {
             if (!self.bullets[1].live || self.reload_cnt < self.bullets[1].flying_cnt)
                 && (!self.bullets[2].live || self.reload_cnt < self.bullets[2].flying_cnt)
             {
                 // プレイヤーに近い列のエイリアンに生き残りがいたら
-                if let Some(i) = alien.alien_index_near_x(player.pos.x) {
+                if let Some(i) = alien.alien_index_near_x(target_x) {
                     // そのエイリアンからrolling shot(自機を狙う)発射
                     self.bullets[seed].fire(alien.index2pos(i), self.speed);
+                    self.target = (self.target + 1) % players.len();
                 }
             }
-        } else if seed == 1 && !self.bullets[seed].live && self.bullets[seed].explosion_cnt == None
+        } else if seed == 1
+            && !self.bullets[seed].live
+            && self.bullets[seed].explosion_cnt.is_none()
         {
             // 自身が画面上に無く、かつ他2種の弾が発射してから一定時間経過した後
             // plunger shot(十字架、ピストン弾)
             if (!self.bullets[0].live || self.reload_cnt < self.bullets[0].flying_cnt)
                 && (!self.bullets[2].live || self.reload_cnt < self.bullets[2].flying_cnt)
             {
-                if let Some(i) = alien.column2index(self.plunger_shot_column_table.take()) {
+                let column = Self::take_column(
+                    &mut self.order,
+                    self.commanded,
+                    &mut self.plunger_shot_column_table,
+                    alien,
+                );
+                if let Some(i) = column.and_then(|column| alien.column2index(column)) {
                     // plunger shot発射
                     self.bullets[seed].fire(alien.index2pos(i), self.speed);
                 }
             }
-        } else if !self.bullets[seed].live && self.bullets[seed].explosion_cnt == None {
+        } else if !self.bullets[seed].live && self.bullets[seed].explosion_cnt.is_none() {
             // 自身が画面上に無く、かつ他2種の弾が発射してから一定時間経過した後
             if (!self.bullets[0].live || self.reload_cnt < self.bullets[0].flying_cnt)
                 && (!self.bullets[1].live || self.reload_cnt < self.bullets[1].flying_cnt)
             {
-                if let Some(i) = alien.column2index(self.squiggly_shot_column_table.take()) {
+                let column = Self::take_column(
+                    &mut self.order,
+                    self.commanded,
+                    &mut self.squiggly_shot_column_table,
+                    alien,
+                );
+                if let Some(i) = column.and_then(|column| alien.column2index(column)) {
                     // squiggly shot発射
                     self.bullets[seed].fire(alien.index2pos(i), self.speed);
                 }
             }
         }
     }
+    // 次に撃つ列を、指示を受ける場合は指示から(無ければNone)、それ以外は発射列表から取り出す
+    // 指示した列が発射までに全滅していたら、最も近い生き残りの列から撃つ
+    fn take_column(
+        order: &mut Option<usize>,
+        commanded: bool,
+        table: &mut TableManage,
+        alien: &Alien,
+    ) -> Option<usize> {
+        if commanded {
+            order
+                .take()
+                .and_then(|column| alien.nearest_live_column(column))
+        } else {
+            Some(table.take())
+        }
+    }
     fn set_reload_cnt(&mut self, score: i32) {
         self.reload_cnt = match score {
             0..=200 => 48,
@@ -321,6 +387,7 @@ impl BulletManage {
     }
 }
 
+#[derive(Clone)]
 struct Explosion {
     pos: IVec2,
     // 爆発エフェクトのスプライト
@@ -359,6 +426,7 @@ impl ArraySprite for Explosion {
     }
 }
 
+#[derive(Clone)]
 pub struct Alien {
     // リファレンスエイリアンの座標
     pub ref_alien_pos: IVec2,
@@ -379,10 +447,9 @@ pub struct Alien {
     pub live_num: i32,
     // ステージ2から9までのリファレンスエイリアンの位置
     table_init_pos_y: Vec<i32>,
-    se: Vec<Sound>,
-    se_volume: f32,
+    // 次に鳴らす侵攻音(0..4)
     se_index: usize,
-    explosion_se: Sound,
+    // 前回侵攻音を鳴らしてからのカウント
     se_interval: i32,
 }
 
@@ -399,17 +466,16 @@ impl Alien {
         high_sprite1: Vec<u8>,
         // 爆発エフェクトのスプライト
         explosion_sprite: Vec<u8>,
-        se: Vec<Sound>,
-        explosion_se: Sound,
     ) -> Self {
-        let mut sprite_list = Vec::new();
-        sprite_list.push(low_sprite0);
-        sprite_list.push(low_sprite1);
-        sprite_list.push(middle_sprite0);
-        sprite_list.push(middle_sprite1);
-        sprite_list.push(high_sprite0);
-        sprite_list.push(high_sprite1);
-        let table_init_pos_y: Vec<i32> = vec![88, 72, 64, 64, 64, 56, 56, 56]
+        let sprite_list = vec![
+            low_sprite0,
+            low_sprite1,
+            middle_sprite0,
+            middle_sprite1,
+            high_sprite0,
+            high_sprite1,
+        ];
+        let table_init_pos_y: Vec<i32> = [88, 72, 64, 64, 64, 56, 56, 56]
             .iter()
             .map(|y| canvas::GAME_HEIGHT - y)
             .collect();
@@ -431,18 +497,12 @@ impl Alien {
             live: vec![true; 55],
             live_num: 55,
             table_init_pos_y,
-            se,
-            se_volume: 0.3,
             se_index: 0,
-            explosion_se,
             se_interval: 0,
         }
     }
-    pub fn set_se_volume(&mut self, volume: i32) {
-        self.se_volume = (volume as f32) / 100.;
-    }
     // エイリアンを初期化する
-    pub fn reset(&mut self, stage: usize) {
+    pub fn reset(&mut self, stage: usize, se: &mut SoundQueue) {
         self.live = vec![true; 55];
         self.live_num = 55;
         self.show_sprite = true;
@@ -459,13 +519,13 @@ impl Alien {
             canvas::GAME_HEIGHT - 112
         };
         self.pre_ref_alien_pos = self.ref_alien_pos;
-        for i in 0..self.se.len() {
-            stop_sound(self.se[i]);
+        for i in 0..4 {
+            se.stop(Se::InvaderStep(i));
         }
     }
-    pub fn update(&mut self, dot_map: &mut DotMap, player_exploding: bool) {
+    pub fn update(&mut self, dot_map: &mut DotMap, player_exploding: bool, se: &mut SoundQueue) {
         // プレイヤーまたはエイリアンが爆発中はすべてのエイリアンを停止させる
-        if player_exploding || self.explosion.effect_cnt != None {
+        if player_exploding || self.explosion.effect_cnt.is_some() {
             self.explosion.update(dot_map);
             return;
         }
@@ -514,22 +574,38 @@ impl Alien {
             self.pre_ref_alien_pos = self.ref_alien_pos;
             // リファレンスエイリアンを移動させる
             self.ref_alien_pos += self.speed;
-            // wasmでは再生しない
-            #[cfg(not(target_arch = "wasm32"))]
             if self.se_interval > 9 {
                 // カーソルエイリアン(に一番近い個体)が動いた時に侵攻音再生
-                play_sound(
-                    self.se[self.se_index],
-                    PlaySoundParams {
-                        looped: false,
-                        volume: self.se_volume,
-                    },
-                );
+                se.play(Se::InvaderStep(self.se_index));
                 self.se_index = (self.se_index + 1) % 4;
                 self.se_interval = 0;
             }
         }
     }
+    // インデックス番号ごとのエイリアンの生存状態
+    pub fn live(&self) -> &[bool] {
+        &self.live
+    }
+    // 生きているエイリアンをすべて描く(交代後に画面を描き直すときに使う)
+    pub fn draw_all(&self, dot_map: &mut DotMap) {
+        for i in (0..self.live.len()).filter(|&i| self.live[i]) {
+            // カーソルより前の個体はこの一巡で、それ以外は前の一巡で描かれたスプライト
+            let sprite_type = if (i < self.i_cursor_alien) == self.show_sprite {
+                0
+            } else {
+                1
+            };
+            let sprite = &self.sprite_list[2 * Alien::ret_alien_type(i) + sprite_type];
+            array_sprite(&mut dot_map.map, self.index2pos(i), sprite);
+        }
+    }
+    // 生きているエイリアンの左上位置の一覧
+    pub fn live_alien_pos(&self) -> Vec<IVec2> {
+        (0..self.live.len())
+            .filter(|&i| self.live[i])
+            .map(|i| self.index2pos(i))
+            .collect()
+    }
     // 一番下のエイリアンがプレイヤーの高さまで侵攻したら真を返す
     pub fn invaded(&self) -> bool {
         canvas::GAME_HEIGHT - 24 <= self.index2pos(self.i_cursor_alien).y
@@ -560,7 +636,7 @@ impl Alien {
         self.speed.y = 0;
     }
     // インデックス番号で指定されたエイリアンを消す
-    pub fn remove(&mut self, dot_map: &mut DotMap, i: usize) {
+    pub fn remove(&mut self, dot_map: &mut DotMap, i: usize, se: &mut SoundQueue) {
         self.live[i] = false;
         let width = self.sprite_list[2 * Alien::ret_alien_type(i)].len();
         let alien_pos = self.index2pos(i);
@@ -574,15 +650,7 @@ impl Alien {
         self.explosion.create_effect(dot_map, alien_pos);
         self.live_num -= 1;
         // 爆発音再生
-        // wasmでは再生しない
-        #[cfg(not(target_arch = "wasm32"))]
-        play_sound(
-            self.explosion_se,
-            PlaySoundParams {
-                looped: false,
-                volume: self.se_volume,
-            },
-        );
+        se.play(Se::InvaderKilled);
     }
     // プレイヤーの弾の座標を引数として、エイリアンに当たった場合はそのエイリアンのインデックス番号を返す
     pub fn pos2index(&self, mut pos: IVec2) -> Option<usize> {
@@ -627,7 +695,7 @@ impl Alien {
         self.column2index(column)
     }
     // 列番号(0..11)のエイリアンが存在していたら一番下の個体のインデックス番号を、全滅していたらNoneを返す
-    fn column2index(&self, column: usize) -> Option<usize> {
+    pub fn column2index(&self, column: usize) -> Option<usize> {
         let mut i = column;
 
         while i < 55 {
@@ -638,6 +706,12 @@ impl Alien {
         }
         None
     }
+    // 指定の列に最も近い、生き残りがいる列を返す(同じ距離なら左の列、全滅していたらNone)
+    pub fn nearest_live_column(&self, column: usize) -> Option<usize> {
+        (0..11)
+            .filter(|&c| self.column2index(c).is_some())
+            .min_by_key(|&c| c.abs_diff(column))
+    }
 
     // エイリアンのインデックス番号から座標を返す
     fn index2pos(&self, i: usize) -> IVec2 {
@@ -689,3 +763,51 @@ impl ArraySprite for Alien {
         &self.sprite_list[2 * Alien::ret_alien_type(self.i_cursor_alien) + sprite_type]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alien() -> Alien {
+        Alien::new(vec![], vec![], vec![], vec![], vec![], vec![], vec![])
+    }
+
+    // 指定の列を全滅させる
+    fn wipe_out(alien: &mut Alien, column: usize) {
+        for i in (column..55).step_by(11) {
+            alien.live[i] = false;
+        }
+    }
+
+    #[test]
+    fn nearest_live_column_skips_wiped_out_columns() {
+        let mut alien = alien();
+        assert_eq!(alien.nearest_live_column(5), Some(5));
+        wipe_out(&mut alien, 5);
+        // 同じ距離なら左の列
+        assert_eq!(alien.nearest_live_column(5), Some(4));
+        wipe_out(&mut alien, 4);
+        assert_eq!(alien.nearest_live_column(5), Some(6));
+        for column in 0..11 {
+            wipe_out(&mut alien, column);
+        }
+        assert_eq!(alien.nearest_live_column(5), None);
+    }
+
+    #[test]
+    fn order_for_wiped_out_column_fires_from_nearest() {
+        let mut alien = alien();
+        let mut bullets = BulletManage::new(vec![]);
+        bullets.commanded = true;
+        wipe_out(&mut alien, 0);
+        bullets.order(0);
+        let column = BulletManage::take_column(
+            &mut bullets.order,
+            bullets.commanded,
+            &mut bullets.squiggly_shot_column_table,
+            &alien,
+        );
+        assert_eq!(column, Some(1));
+        assert_eq!(bullets.order, None);
+    }
+}
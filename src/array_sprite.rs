@@ -13,16 +13,16 @@ pub trait ArraySprite {
         let sprite = self.sprite();
         let char_y = (pos.y / 8) as usize;
         let char_offset_bit = (pos.y % 8) as u8;
-        for x in 0..sprite.len() {
+        for (x, &byte) in sprite.iter().enumerate() {
             // 1にしたいbitには1、透過部分には0をおく
-            let bit_mask: u8 = sprite[x] << char_offset_bit;
+            let bit_mask: u8 = byte << char_offset_bit;
             dot_map.map[char_y][pos.x as usize + x] |= bit_mask;
         }
         if char_offset_bit != 0 {
             // 下側にはみ出した部分
-            for x in 0..sprite.len() {
+            for (x, &byte) in sprite.iter().enumerate() {
                 // 1にしたいbitには1、透過部分には0をおく
-                let bit_mask = sprite[x] >> (8 - char_offset_bit);
+                let bit_mask = byte >> (8 - char_offset_bit);
                 dot_map.map[char_y + 1][pos.x as usize + x] |= bit_mask;
             }
         }
@@ -41,16 +41,16 @@ pub trait ArraySprite {
 
         let char_y = (pos.y / 8) as usize;
         let char_offset_bit = (pos.y % 8) as u8;
-        for x in 0..sprite.len() {
+        for (x, &byte) in sprite.iter().enumerate() {
             // 0にしたいbitには0、透過部分には1をおく
-            let bit_mask: u8 = !(sprite[x] << char_offset_bit);
+            let bit_mask: u8 = !(byte << char_offset_bit);
             dot_map.map[char_y][pos.x as usize + x] &= bit_mask;
         }
         if char_offset_bit != 0 {
             // 下側にはみ出した部分
-            for x in 0..sprite.len() {
+            for (x, &byte) in sprite.iter().enumerate() {
                 // 0にしたいbitには0、透過部分には1をおく
-                let bit_mask = !(sprite[x] >> (8 - char_offset_bit));
+                let bit_mask = !(byte >> (8 - char_offset_bit));
                 dot_map.map[char_y + 1][pos.x as usize + x] &= bit_mask;
             }
         }
@@ -62,8 +62,7 @@ pub trait ArraySprite {
         let char_y = (pos.y / 8) as usize;
         let offset_bit = (pos.y % 8) as u8;
         // 移動した弾の部分のビットマスクを作る
-        for i in 0..self.sprite().len() {
-            let bit_mask = sprite[i] & 0b1111_1111;
+        for &bit_mask in sprite {
             // ビットがバイトの境界をまたぐときの上下それぞれの判定
             let high = dot_map.map[char_y][pos.x as usize] & (bit_mask << offset_bit) != 0;
             let low = if offset_bit != 0 {
@@ -81,18 +80,29 @@ pub trait ArraySprite {
 }
 
 // バイト境界をまたがない物体の描画を透過なしで行う(上書き)
-pub fn array_sprite(dot_map: &mut Vec<Vec<u8>>, pos: IVec2, sprite: &[u8]) {
+pub fn array_sprite(dot_map: &mut [Vec<u8>], pos: IVec2, sprite: &[u8]) {
     let char_y = (pos.y / 8) as usize;
-    for dx in 0..sprite.len() {
-        dot_map[char_y][pos.x as usize + dx] = sprite[dx];
+    for (dx, &byte) in sprite.iter().enumerate() {
+        dot_map[char_y][pos.x as usize + dx] = byte;
     }
 }
 
 // 引数の座標からスプライトのサイズの矩形部分を消す
-pub fn erase(dot_map: &mut Vec<Vec<u8>>, pos: IVec2, sprite: &[u8]) {
+pub fn erase(dot_map: &mut [Vec<u8>], pos: IVec2, sprite: &[u8]) {
     // 前回描画した部分を0で消す
     let char_y = (pos.y / 8) as usize;
     for dx in 0..sprite.len() {
         dot_map[char_y][pos.x as usize + dx] = 0;
     }
 }
+
+// 文字列を1文字8ドット幅で左から並べて描画する(対応する文字が無い場合は空白にする)
+pub fn array_string(dot_map: &mut [Vec<u8>], mut pos: IVec2, text: &str, font: &[Vec<u8>]) {
+    for c in text.chars() {
+        match crate::sprite::char2index(c) {
+            Some(i) => array_sprite(dot_map, pos, &font[i]),
+            None => erase(dot_map, pos, &[0; 8]),
+        }
+        pos.x += 8;
+    }
+}
@@ -0,0 +1,185 @@
+use crate::array_sprite::{array_sprite, array_string};
+use crate::bot::{Bot, GameView, ReferenceBot};
+use crate::canvas;
+use crate::dot_map::DotMap;
+use crate::game::{Difficulty, Game, Scene};
+use crate::high_score::HighScore;
+use crate::sprite::Sprites;
+use macroquad::prelude::IVec2;
+
+// タイトル画面で操作が無いまま次のページに移るまでのカウント数
+const TITLE_CNT: i32 = 10 * 60;
+// 得点表、ハイスコア表を表示するカウント数
+const TABLE_CNT: i32 = 8 * 60;
+// デモプレイの最長のカウント数
+const DEMO_CNT: i32 = 60 * 60;
+// 得点表の文字を1文字ずつ表示する間隔
+const TYPE_INTERVAL_CNT: i32 = 4;
+
+// アトラクトモードのページ
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Page {
+    Title,      // タイトル
+    ScoreTable, // 得点表
+    Demo,       // デモプレイ
+    HighScore,  // ハイスコア表
+}
+
+// 得点表の1行
+struct ScoreRow {
+    sprite: Vec<u8>,
+    text: &'static str,
+}
+
+// タイトル画面で操作が無いとき、得点表、デモプレイ、ハイスコア表を順に表示する
+pub struct Attract {
+    pub page: Page,
+    cnt: i32, // ページを表示してからのカウント
+    pub plane: DotMap,
+    font: Vec<Vec<u8>>,
+    score_rows: Vec<ScoreRow>,
+    sprites: Sprites,   // デモプレイのゲームを作るためのスプライト
+    demo: Option<Game>, // デモプレイ中のゲーム
+    demo_bot: ReferenceBot,
+}
+
+impl Attract {
+    pub fn new(sprites: &Sprites) -> Self {
+        let row = |sprite: &Vec<u8>, text| ScoreRow {
+            sprite: sprite.clone(),
+            text,
+        };
+        Attract {
+            page: Page::Title,
+            cnt: 0,
+            plane: DotMap::game(),
+            font: sprites.font.clone(),
+            score_rows: vec![
+                row(&sprites.ufo, "=? MYSTERY"),
+                row(&sprites.squid[0], "=30 POINTS"),
+                row(&sprites.crab[0], "=20 POINTS"),
+                row(&sprites.octopus[0], "=10 POINTS"),
+            ],
+            sprites: sprites.clone(),
+            demo: None,
+            demo_bot: ReferenceBot::default(),
+        }
+    }
+    // タイトルのページに戻す
+    pub fn reset(&mut self) {
+        self.set_page(Page::Title);
+    }
+    fn set_page(&mut self, page: Page) {
+        self.page = page;
+        self.cnt = 0;
+        self.demo = None;
+        self.plane.all_clear();
+    }
+    // デモプレイ中ならそのゲームを返す
+    pub fn demo(&self) -> Option<&Game> {
+        self.demo.as_ref()
+    }
+    // 1フレーム分進める
+    pub fn update(&mut self, high_score: &HighScore) {
+        self.cnt += 1;
+        match self.page {
+            Page::Title => {
+                if TITLE_CNT <= self.cnt {
+                    self.set_page(Page::ScoreTable);
+                }
+            }
+            Page::ScoreTable => {
+                self.draw_score_table();
+                if TABLE_CNT <= self.cnt {
+                    self.set_page(Page::Demo);
+                    let mut demo = Game::new(&self.sprites, high_score.clone(), Difficulty::Normal);
+                    demo.start();
+                    self.demo = Some(demo);
+                    self.demo_bot = ReferenceBot::default();
+                }
+            }
+            Page::Demo => {
+                let Some(demo) = self.demo.as_mut() else {
+                    return;
+                };
+                let input = self.demo_bot.input(&GameView::new(demo));
+                demo.update(&input);
+                // デモプレイの音は鳴らさない
+                demo.take_sound_requests();
+                // 1機やられるか一定時間経ったら終わる
+                let finished = demo.player.life < demo.player.start_life
+                    || matches!(demo.scene, Scene::Gameover(_) | Scene::Title)
+                    || DEMO_CNT <= self.cnt;
+                if finished {
+                    self.set_page(Page::HighScore);
+                    self.draw_high_score(high_score);
+                }
+            }
+            Page::HighScore => {
+                if TABLE_CNT <= self.cnt {
+                    self.set_page(Page::Title);
+                }
+            }
+        }
+    }
+    // 「*SCORE ADVANCE TABLE*」と各キャラクターの得点を1文字ずつ表示する
+    fn draw_score_table(&mut self) {
+        let mut rest = (self.cnt / TYPE_INTERVAL_CNT) as usize;
+        let title = "*SCORE ADVANCE TABLE*";
+        let title_x = (canvas::GAME_WIDTH - 8 * title.len() as i32) / 2;
+        array_string(
+            &mut self.plane.map,
+            IVec2::new(title_x, 64),
+            title,
+            &self.font,
+        );
+        for (i, row) in self.score_rows.iter().enumerate() {
+            let y = 96 + 16 * i as i32;
+            // スプライトは行の文字を表示し始めたら表示する
+            if 0 < rest {
+                let x = 76 - row.sprite.len() as i32 / 2;
+                array_sprite(&mut self.plane.map, IVec2::new(x, y), &row.sprite);
+            }
+            let len = rest.min(row.text.len());
+            let text = &row.text[..len];
+            array_string(&mut self.plane.map, IVec2::new(88, y), text, &self.font);
+            rest -= len;
+        }
+    }
+    fn draw_high_score(&mut self, high_score: &HighScore) {
+        array_string(
+            &mut self.plane.map,
+            IVec2::new(68, 16),
+            "HIGH SCORES",
+            &self.font,
+        );
+        for (i, entry) in high_score.entries.iter().enumerate() {
+            let text = format!("{:>2}.{} {:>5}", i + 1, entry.name, entry.score);
+            // 画面の幅からはみ出す分は表示しない
+            let len = (self.plane.width() / 8) as usize;
+            let text: String = text.chars().take(len).collect();
+            let x = ((self.plane.width() - 8 * text.chars().count() as i32) / 2).max(0);
+            let y = 40 + 16 * i as i32;
+            array_string(&mut self.plane.map, IVec2::new(x, y), &text, &self.font);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::high_score::Entry;
+
+    #[test]
+    fn long_high_score_rows_stay_on_the_plane() {
+        let mut attract = Attract::new(&Sprites::load().unwrap());
+        let high_score = HighScore {
+            entries: vec![Entry {
+                name: "A".repeat(40),
+                score: i32::MAX,
+            }],
+        };
+        // 画面からはみ出す行でも描画できる
+        attract.draw_high_score(&high_score);
+    }
+}
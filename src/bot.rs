@@ -0,0 +1,108 @@
+use crate::canvas;
+use crate::game::{Game, Scene};
+use crate::input::Input;
+use macroquad::prelude::IVec2;
+
+// ボットに見せるゲームの状態(読み取り専用)
+pub struct GameView<'a> {
+    pub scene: Scene,
+    pub player_x: i32,             // プレイヤーの左端のX座標
+    pub player_exploding: bool,    // プレイヤーが爆発中なら真
+    pub player_bullet_live: bool,  // プレイヤーの弾が画面上にあれば真
+    pub ref_alien_pos: IVec2,      // リファレンスエイリアンの座標
+    pub alien_live: &'a [bool],    // インデックス番号ごとのエイリアンの生存状態
+    pub alien_bullets: Vec<IVec2>, // 画面上にあるエイリアンの弾の左上位置
+    pub ufo_pos: Option<IVec2>,    // 画面上にいる場合はUFOの左上位置
+}
+
+impl<'a> GameView<'a> {
+    pub fn new(game: &'a Game) -> Self {
+        GameView {
+            scene: game.scene,
+            player_x: game.player.pos.x,
+            player_exploding: game.player.explosion_cnt.is_some(),
+            player_bullet_live: game.player_bullet.is_live(),
+            ref_alien_pos: game.alien.ref_alien_pos,
+            alien_live: game.alien.live(),
+            alien_bullets: game.alien_bullets.live_bullet_pos(),
+            ufo_pos: game.ufo.pos(),
+        }
+    }
+}
+
+// 毎フレームゲームの状態を見て入力を決める
+pub trait Bot {
+    fn input(&mut self, view: &GameView) -> Input;
+}
+
+// プレイヤーの幅
+const PLAYER_WIDTH: i32 = 16;
+// プレイヤーの上端のY座標
+const PLAYER_Y: i32 = canvas::GAME_HEIGHT - 8 * 3;
+// この高さより下に来たエイリアンの弾を避ける
+const DANGER_Y: i32 = PLAYER_Y - 48;
+// 弾の幅とプレイヤーの左右の余裕
+const DANGER_MARGIN: i32 = 6;
+
+// 弾を避けつつ、一番近い列のエイリアンを狙う参考実装のボット
+#[derive(Default)]
+pub struct ReferenceBot {
+    pre_fire: bool, // 前回射撃ボタンを押していたら真
+}
+
+impl Bot for ReferenceBot {
+    fn input(&mut self, view: &GameView) -> Input {
+        let mut input = Input::default();
+        if matches!(view.scene, Scene::SelectPlayers(_) | Scene::EnterName) {
+            // 人数選択とイニシャル入力は押し直すたびに確定するので、交互に押す
+            input.fire = !self.pre_fire;
+            self.pre_fire = input.fire;
+            return input;
+        }
+        self.pre_fire = false;
+        if view.player_exploding {
+            return input;
+        }
+        let left = view.player_x - DANGER_MARGIN;
+        let right = view.player_x + PLAYER_WIDTH + DANGER_MARGIN;
+        let center = view.player_x + PLAYER_WIDTH / 2;
+        // 近づいてくる弾があれば避ける
+        let danger = view
+            .alien_bullets
+            .iter()
+            .filter(|b| DANGER_Y <= b.y && b.y < PLAYER_Y + 8 && left <= b.x && b.x <= right)
+            .max_by_key(|b| b.y);
+        if let Some(bullet) = danger {
+            // 弾と反対側へ逃げる(壁際なら逆に抜ける)
+            let go_left = center <= bullet.x + 1;
+            let at_left_wall = view.player_x <= 8;
+            let at_right_wall = canvas::GAME_WIDTH - 8 <= view.player_x + PLAYER_WIDTH;
+            if go_left && !at_left_wall || at_right_wall {
+                input.left = true;
+            } else {
+                input.right = true;
+            }
+            return input;
+        }
+        // 生きているエイリアンがいる列のうち一番近い列の下に移動して撃つ
+        let target = (0..11)
+            .filter(|column| (0..5).any(|row| view.alien_live[row * 11 + column]))
+            .map(|column| view.ref_alien_pos.x + 16 * column as i32 + 8)
+            .min_by_key(|x| (x - center).abs());
+        if let Some(x) = target {
+            // 弾はプレイヤーの左端から7ドットの位置から発射される
+            let dx = x - (view.player_x + 7);
+            input.left = dx < -1;
+            input.right = 1 < dx;
+            input.fire = dx.abs() <= 2 && !view.player_bullet_live;
+        }
+        // UFOの真下にいれば狙い撃つ
+        if let Some(ufo) = view.ufo_pos {
+            let shot_x = view.player_x + 7;
+            if ufo.x <= shot_x && shot_x < ufo.x + 16 && !view.player_bullet_live {
+                input.fire = true;
+            }
+        }
+        input
+    }
+}
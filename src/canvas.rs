@@ -1,8 +1,4 @@
-use macroquad::{
-    prelude::Color,
-    shapes::draw_rectangle,
-    window::{screen_height, screen_width},
-};
+use macroquad::window::{get_internal_gl, screen_height, screen_width};
 // 画面の幅(文字単位)
 const CHAR_WIDTH: i32 = 28;
 // 画面の上部分（スコアなどの表示用）のドット単位の大きさ(28文字x4文字)
@@ -16,18 +12,43 @@ pub const GAME_HEIGHT: i32 = 8 * 26;
 pub const BOTTOM_WIDTH: i32 = 8 * CHAR_WIDTH;
 pub const BOTTOM_HEIGHT: i32 = 8 * 2;
 
-// 1ドットを何ピクセル四方で表示するか(pixel / dot)
+// 画面全体のドット単位の大きさ
+pub const SCREEN_WIDTH: i32 = GAME_WIDTH;
+pub const SCREEN_HEIGHT: i32 = TOP_HEIGHT + GAME_HEIGHT + BOTTOM_HEIGHT;
+
+// 起動時に1ドットを何ピクセル四方で表示するか(pixel / dot)
 #[cfg(not(target_arch = "wasm32"))]
 pub const SCALE: i32 = 3;
 // wasm版は少し小さくする
 #[cfg(target_arch = "wasm32")]
 pub const SCALE: i32 = 2;
 
-// 指定した色を画面全体の上にかぶせる(alpha値を指定可能)
-pub fn draw_screen(color: Color) {
-    draw_rectangle(0., 0., screen_width(), screen_height(), color);
+// 現在のウィンドウに収まる最大の整数倍率(pixel / dot)
+pub fn scale() -> i32 {
+    let x = screen_width() as i32 / SCREEN_WIDTH;
+    let y = screen_height() as i32 / SCREEN_HEIGHT;
+    x.min(y).max(1)
 }
-// ドット座標を最終的なピクセル座標に変換
+// ドット単位の長さをピクセル単位の長さに変換
 pub fn dot2pix(dot_scale: i32) -> f32 {
-    (dot_scale * SCALE) as f32
+    (dot_scale * scale()) as f32
+}
+// 画面を中央に置いたときの左上のピクセル座標(余白は黒帯になる)
+fn origin() -> (f32, f32) {
+    let x = (screen_width() - dot2pix(SCREEN_WIDTH)) / 2.;
+    let y = (screen_height() - dot2pix(SCREEN_HEIGHT)) / 2.;
+    (x.max(0.).floor(), y.max(0.).floor())
+}
+// ドット単位のX座標を最終的なピクセル座標に変換
+pub fn dot2x(x: i32) -> f32 {
+    origin().0 + dot2pix(x)
+}
+// ドット単位のY座標を最終的なピクセル座標に変換
+pub fn dot2y(y: i32) -> f32 {
+    origin().1 + dot2pix(y)
+}
+// 全画面表示を切り替える
+pub fn set_fullscreen(fullscreen: bool) {
+    // macroquad 0.3では全画面表示の切り替えはminiquadのコンテキストからのみ行える
+    unsafe { get_internal_gl().quad_context.set_fullscreen(fullscreen) };
 }
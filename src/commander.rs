@@ -0,0 +1,55 @@
+use crate::alien::{Alien, BulletManage};
+use crate::input::Input;
+use crate::ufo::Ufo;
+
+// 対戦プレイでエイリアン側を操作する2人目
+// 左右で弾を撃たせる列を選び、射撃ボタンでその列から撃たせ、UFOボタンでUFOを発進させる
+pub struct Commander {
+    column: usize,    // 選択中の列(0..11)
+    pre_input: Input, // 前回の入力(押された瞬間だけ反応させる)
+}
+
+impl Commander {
+    pub fn new() -> Self {
+        Commander {
+            column: 5,
+            pre_input: Input::default(),
+        }
+    }
+    pub fn update(
+        &mut self,
+        input: &Input,
+        alien: &Alien,
+        alien_bullets: &mut BulletManage,
+        ufo: &mut Ufo,
+    ) {
+        let pre = self.pre_input;
+        self.pre_input = *input;
+        // 選択中の列が全滅したら、最も近い生き残りの列に移る
+        if let Some(column) = alien.nearest_live_column(self.column) {
+            self.column = column;
+        }
+        // 全滅した列は飛ばして、生き残りがいる隣の列に移る
+        let live = |column: &usize| alien.column2index(*column).is_some();
+        if input.left2 && !pre.left2 {
+            if let Some(column) = (0..self.column).rev().find(live) {
+                self.column = column;
+            }
+        }
+        if input.right2 && !pre.right2 {
+            if let Some(column) = (self.column + 1..11).find(live) {
+                self.column = column;
+            }
+        }
+        if input.fire2 && !pre.fire2 {
+            alien_bullets.order(self.column);
+        }
+        if input.ufo2 && !pre.ufo2 && ufo.can_launch(alien.live_num) {
+            ufo.launch();
+        }
+    }
+    // 選択中の列の左端のX座標
+    pub fn column_x(&self, alien: &Alien) -> i32 {
+        alien.ref_alien_pos.x + 16 * self.column as i32
+    }
+}
@@ -0,0 +1,305 @@
+use crate::array_sprite::array_string;
+use crate::dot_map::DotMap;
+use macroquad::prelude::*;
+
+// キーを割り当てられる操作
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Left,     // 左移動
+    Right,    // 右移動
+    Fire,     // 射撃
+    Pause,    // ポーズ切り替え
+    MenuUp,   // メニューのカーソルを上に移動
+    MenuDown, // メニューのカーソルを下に移動
+    Left2,    // 2人目の左移動(対戦プレイでは撃たせる列の選択)
+    Right2,   // 2人目の右移動(対戦プレイでは撃たせる列の選択)
+    Fire2,    // 2人目の射撃(対戦プレイでは選んだ列から撃たせる)
+    Ufo2,     // 対戦プレイの2人目のUFO発進
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::Left,
+        Action::Right,
+        Action::Fire,
+        Action::Pause,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::Left2,
+        Action::Right2,
+        Action::Fire2,
+        Action::Ufo2,
+    ];
+    // 設定ファイルでの名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Fire => "fire",
+            Action::Pause => "pause",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::Left2 => "p2_left",
+            Action::Right2 => "p2_right",
+            Action::Fire2 => "p2_fire",
+            Action::Ufo2 => "p2_ufo",
+        }
+    }
+    // 操作設定画面での表示名
+    fn label(self) -> &'static str {
+        match self {
+            Action::Left => "LEFT",
+            Action::Right => "RIGHT",
+            Action::Fire => "FIRE",
+            Action::Pause => "PAUSE",
+            Action::MenuUp => "MENU UP",
+            Action::MenuDown => "MENU DOWN",
+            Action::Left2 => "P2 LEFT",
+            Action::Right2 => "P2 RIGHT",
+            Action::Fire2 => "P2 FIRE",
+            Action::Ufo2 => "P2 UFO",
+        }
+    }
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+// 割り当てられるキー
+const KEYS: [KeyCode; 59] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightAlt,
+    KeyCode::Kp4,
+    KeyCode::Kp6,
+    KeyCode::Kp8,
+];
+
+fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEYS.into_iter().find(|&key| key_name(key) == name)
+}
+
+// 操作ごとのキーの割り当て
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Controls {
+    keys: [Vec<KeyCode>; Action::ALL.len()], // Action::ALLと同じ順
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Controls {
+            keys: [
+                vec![KeyCode::A, KeyCode::Left],
+                vec![KeyCode::D, KeyCode::Right],
+                vec![KeyCode::Z, KeyCode::Space, KeyCode::Enter],
+                vec![KeyCode::Escape],
+                vec![KeyCode::W, KeyCode::Up],
+                vec![KeyCode::S, KeyCode::Down],
+                vec![KeyCode::J],
+                vec![KeyCode::L],
+                vec![KeyCode::K],
+                vec![KeyCode::I],
+            ],
+        }
+    }
+}
+
+impl Controls {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        &self.keys[action as usize]
+    }
+    // 割り当てられたキーのどれかが押されていれば真
+    pub fn is_down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|&key| is_key_down(key))
+    }
+    // 割り当てられたキーのどれかがこのフレームで押されたら真
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|&key| is_key_pressed(key))
+    }
+    // 「操作名 キー名 キー名…」の形式で1つの操作の割り当てを変える
+    pub fn parse_keys(&mut self, text: &str) -> Result<(), String> {
+        let mut words = text.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let action =
+            Action::from_name(name).ok_or_else(|| format!("{}という操作はありません。", name))?;
+        let keys = words
+            .map(|word| {
+                key_from_name(word).ok_or_else(|| format!("{}というキーはありません。", word))
+            })
+            .collect::<Result<_, _>>()?;
+        self.keys[action as usize] = keys;
+        Ok(())
+    }
+    // 操作ごとに「操作名 キー名 キー名…」の形式で並べる
+    pub fn to_lines(&self) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .map(|&action| {
+                let keys: Vec<String> = self.keys(action).iter().map(|&k| key_name(k)).collect();
+                format!("{} {}", action.name(), keys.join(" "))
+            })
+            .collect()
+    }
+}
+
+// メニューの操作
+// 割り当てを壊してもメニューを操作できるよう、矢印キー、Enter、Escキーでも操作できる
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct MenuInput {
+    pub up: bool,     // カーソルを上に移動
+    pub down: bool,   // カーソルを下に移動
+    pub left: bool,   // 値を減らす
+    pub right: bool,  // 値を増やす
+    pub select: bool, // 決定
+    pub back: bool,   // 戻る
+}
+
+impl MenuInput {
+    // このフレームで押されたキーから入力を作る
+    pub fn from_keyboard(controls: &Controls) -> Self {
+        MenuInput {
+            up: controls.is_pressed(Action::MenuUp) || is_key_pressed(KeyCode::Up),
+            down: controls.is_pressed(Action::MenuDown) || is_key_pressed(KeyCode::Down),
+            left: controls.is_pressed(Action::Left) || is_key_pressed(KeyCode::Left),
+            right: controls.is_pressed(Action::Right) || is_key_pressed(KeyCode::Right),
+            select: controls.is_pressed(Action::Fire) || is_key_pressed(KeyCode::Enter),
+            back: controls.is_pressed(Action::Pause) || is_key_pressed(KeyCode::Escape),
+        }
+    }
+    // カーソル位置を上下に動かす(端で反対側に回る)
+    pub fn move_cursor(&self, cursor: usize, len: usize) -> usize {
+        if self.up {
+            (cursor + len - 1) % len
+        } else if self.down {
+            (cursor + 1) % len
+        } else {
+            cursor
+        }
+    }
+}
+
+// 操作の割り当てを変える画面
+// 上下で操作を選び、決定の後に押したキーをその操作に割り当てる
+pub struct ControlsScreen {
+    cursor: usize, // 選択中の操作
+    waiting: bool, // 割り当てるキーの入力待ちなら真
+}
+
+impl ControlsScreen {
+    pub fn new() -> Self {
+        ControlsScreen {
+            cursor: 0,
+            waiting: false,
+        }
+    }
+    // 画面を閉じる場合は偽を返す
+    pub fn update(&mut self, controls: &mut Controls) -> bool {
+        if self.waiting {
+            if let Some(key) = get_last_key_pressed() {
+                if KEYS.contains(&key) {
+                    controls.keys[self.cursor] = vec![key];
+                }
+                self.waiting = false;
+            }
+            return true;
+        }
+        let menu = MenuInput::from_keyboard(controls);
+        self.cursor = menu.move_cursor(self.cursor, Action::ALL.len());
+        if menu.select {
+            self.waiting = true;
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            // すべて既定の割り当てに戻す
+            *controls = Controls::default();
+        }
+        !menu.back
+    }
+    pub fn draw(&self, controls: &Controls, plane: &mut DotMap, font: &[Vec<u8>]) {
+        plane.all_clear();
+        array_string(&mut plane.map, IVec2::new(80, 24), "CONTROLS", font);
+        for (i, &action) in Action::ALL.iter().enumerate() {
+            // 文字は8ドット単位の行に描かれるので、1行ずつ詰めて並べる
+            let y = 48 + 8 * i as i32;
+            if i == self.cursor {
+                array_string(&mut plane.map, IVec2::new(8, y), ">", font);
+            }
+            array_string(&mut plane.map, IVec2::new(24, y), action.label(), font);
+            let keys = if i == self.cursor && self.waiting {
+                "PRESS A KEY".to_owned()
+            } else {
+                let keys: Vec<String> =
+                    controls.keys(action).iter().map(|&k| key_name(k)).collect();
+                keys.join(" ").to_uppercase()
+            };
+            // 画面の右端ではみ出す分は表示しない
+            let x = 104;
+            let len = ((plane.width() - x) / 8) as usize;
+            let keys: String = keys.chars().take(len).collect();
+            array_string(&mut plane.map, IVec2::new(x, y), &keys, font);
+        }
+        array_string(&mut plane.map, IVec2::new(24, 144), "FIRE: CHANGE", font);
+        array_string(
+            &mut plane.map,
+            IVec2::new(24, 152),
+            "BACKSPACE: RESET",
+            font,
+        );
+        array_string(&mut plane.map, IVec2::new(24, 160), "PAUSE: BACK", font);
+    }
+}
@@ -1,106 +1,142 @@
 use crate::canvas;
-use macroquad::texture::{FilterMode, Texture2D};
-use std::io::Write;
+use crate::palette::Palette;
+use std::ops::Range;
 
+// ドットの色の決め方
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorRule {
+    Top,    // パレットの画面上部の色
+    Bottom, // パレットの画面下部の色
+    Rows,   // パレットのゲーム画面の行ごとの色(高さに応じて色を変える)
+}
+
+impl ColorRule {
+    fn color(self, palette: &Palette, char_y: usize) -> [u8; 4] {
+        match self {
+            ColorRule::Top => palette.top,
+            ColorRule::Bottom => palette.bottom,
+            ColorRule::Rows => palette.game_rows[char_y],
+        }
+    }
+}
+
+// 色の決め方によらず、指定した色で描く範囲
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ColorArea {
+    pub char_y: usize, // 文字単位の行
+    pub x: Range<i32>, // ドット単位のX座標の範囲
+    pub color: [u8; 4],
+}
+
+// ゲーム画面、画面上部、画面下部などのドット単位で描く領域
 pub struct DotMap {
     // ドット単位の処理をする範囲
     // 上からy文字目、左からxドット目にあるu8はmap[y][x]
-    // 横8x28、縦26個のu8がある二次元配列
+    // ゲーム画面なら横8x28、縦26個のu8がある二次元配列
     pub map: Vec<Vec<u8>>,
-    pub bottom: Vec<Vec<u8>>,
+    width: i32,  // ドット単位の幅
+    height: i32, // ドット単位の高さ(8の倍数)
+    rule: ColorRule,
 }
 
 impl DotMap {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32, rule: ColorRule) -> Self {
         // 0クリアしたドットマップを生成
         DotMap {
-            map: vec![vec![0; canvas::GAME_WIDTH as usize]; (canvas::GAME_HEIGHT / 8) as usize],
-            bottom: vec![
-                vec![0; canvas::BOTTOM_WIDTH as usize];
-                (canvas::BOTTOM_HEIGHT / 8) as usize
-            ],
+            map: vec![vec![0; width as usize]; (height / 8) as usize],
+            width,
+            height,
+            rule,
         }
     }
+    // メインのゲーム画面
+    pub fn game() -> Self {
+        Self::new(canvas::GAME_WIDTH, canvas::GAME_HEIGHT, ColorRule::Rows)
+    }
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+    pub fn height(&self) -> i32 {
+        self.height
+    }
     // すべて消す
     pub fn all_clear(&mut self) {
-        self.map = vec![vec![0; canvas::GAME_WIDTH as usize]; (canvas::GAME_HEIGHT / 8) as usize]
+        for row in self.map.iter_mut() {
+            row.fill(0);
+        }
+    }
+    // 指定したドット単位の座標のドットが1なら真
+    pub fn dot(&self, x: i32, y: i32) -> bool {
+        self.map[(y / 8) as usize][x as usize] & (1 << (y % 8)) != 0
     }
     // 指定したドット単位のY座標のすべてを1にして水平の線を引く
     pub fn draw_holizon_line(&mut self, y: i32) {
         let y = y as usize;
-        let char_pos_y = y / 8;
         let mask_val: u8 = 1 << (y % 8);
-        for i in 0..canvas::GAME_WIDTH as usize {
-            self.map[char_pos_y][i] = self.map[char_pos_y][i] | mask_val;
+        for dots in self.map[y / 8].iter_mut() {
+            *dots |= mask_val;
         }
     }
     // DotMapを1ピクセル4バイトでrgbaを表し、u8のベクタにまとめる
-    fn convert_to_color_bytes(&self, player_exploding: bool) -> Vec<u8> {
-        let mut color_bytes: Vec<u8> = Vec::new();
-        for i_char in 0..(canvas::GAME_HEIGHT / 8) as usize {
+    // areasの範囲はそれぞれの色で描く
+    pub fn convert_to_color_bytes(
+        &self,
+        palette: &Palette,
+        player_exploding: bool,
+        areas: &[ColorArea],
+    ) -> Vec<u8> {
+        let mut color_bytes: Vec<u8> = Vec::with_capacity((self.width * self.height * 4) as usize);
+        for (i_char, row) in self.map.iter().enumerate() {
+            let color = self.rule.color(palette, i_char);
+            let row_areas: Vec<&ColorArea> = areas.iter().filter(|a| a.char_y == i_char).collect();
             for bit in 0..8 {
-                for pos_x in 0..canvas::GAME_WIDTH as usize {
-                    if self.map[i_char][pos_x] & (1 << bit) == 0 {
-                        color_bytes.write(&[0, 0, 0, 255]).unwrap();
+                for (x, dots) in row.iter().enumerate() {
+                    if dots & (1 << bit) == 0 {
+                        color_bytes.extend_from_slice(&palette.background);
+                    } else if player_exploding {
+                        // プレイヤーが爆発中はすべて赤にする
+                        color_bytes.extend_from_slice(&palette.exploding);
+                    } else if let Some(area) = row_areas.iter().find(|a| a.x.contains(&(x as i32)))
+                    {
+                        color_bytes.extend_from_slice(&area.color);
                     } else {
-                        if player_exploding {
-                            // プレイヤーが爆発中はすべて赤にする
-                            color_bytes.write(&set_color(Color::Red)).unwrap();
-                        } else {
-                            // 高さに応じて色を変える
-                            color_bytes.write(&pos2rgba(i_char)).unwrap();
-                        }
+                        color_bytes.extend_from_slice(&color);
                     }
                 }
             }
         }
         color_bytes
     }
-    pub fn dot_map2texture(&self, player_exploding: bool) -> Texture2D {
-        let rgba = self.convert_to_color_bytes(player_exploding);
-        rgba2texture(rgba)
-    }
 }
 
-// RGBAデータをテクスチャデータに変換
-fn rgba2texture(rgba: Vec<u8>) -> Texture2D {
-    let texture =
-        Texture2D::from_rgba8(canvas::GAME_WIDTH as u16, canvas::GAME_HEIGHT as u16, &rgba);
-    texture.set_filter(FilterMode::Nearest);
-    texture
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub enum Color {
-    Red,       // 赤色
-    Purple,    // 紫色
-    BLUE,      // 青色
-    Green,     // 緑色
-    Turquoise, // 水色
-    Yellow,    // 黄色
-    White,     // 白色
-}
-// 指定した色に対応するrgbaの値を返す
-pub fn set_color(color: Color) -> [u8; 4] {
-    match color {
-        Color::Red => [210, 0, 0, 255],          // 赤色
-        Color::Purple => [220, 20, 230, 255],    // 紫色
-        Color::BLUE => [83, 83, 241, 255],       // 青色
-        Color::Green => [98, 222, 109, 255],     // 緑色
-        Color::Turquoise => [68, 200, 210, 255], // 水色
-        Color::Yellow => [220, 210, 30, 255],    // 黄色
-        Color::White => [220, 220, 220, 255],    // 白色
+    // 指定したドットの色
+    fn pixel(bytes: &[u8], width: i32, x: i32, y: i32) -> [u8; 4] {
+        let i = 4 * (y * width + x) as usize;
+        bytes[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn color_areas_override_the_rule() {
+        let palette = Palette::original();
+        let mut plane = DotMap::new(16, 16, ColorRule::Top);
+        plane.map[1].fill(0xff);
+        let areas = [ColorArea {
+            char_y: 1,
+            x: 4..8,
+            color: [1, 2, 3, 255],
+        }];
+        let bytes = plane.convert_to_color_bytes(&palette, false, &areas);
+        assert_eq!(pixel(&bytes, 16, 4, 8), [1, 2, 3, 255]);
+        assert_eq!(pixel(&bytes, 16, 7, 15), [1, 2, 3, 255]);
+        assert_eq!(pixel(&bytes, 16, 8, 8), palette.top);
+        // ドットが無い部分は背景色のまま
+        assert_eq!(pixel(&bytes, 16, 4, 0), palette.background);
+        // 爆発中はすべて爆発の色
+        let bytes = plane.convert_to_color_bytes(&palette, true, &areas);
+        assert_eq!(pixel(&bytes, 16, 4, 8), palette.exploding);
     }
-}
-// 引数の位置に対応したrgba値を返す
-fn pos2rgba(char_y: usize) -> [u8; 4] {
-    let color = match char_y {
-        0 | 20..=22 | 25 => Color::Red,
-        1 | 12..=15 => Color::Purple,
-        2 | 3 => Color::BLUE,
-        4..=7 => Color::Green,
-        8..=11 | 23 | 24 => Color::Turquoise,
-        16..=19 => Color::Yellow,
-        _ => panic!("文字単位で{}行目は画面からはみだしています。", char_y),
-    };
-    set_color(color)
 }
@@ -0,0 +1,274 @@
+use crate::bot::{Bot, GameView, ReferenceBot};
+use crate::error::AssetError;
+use crate::game::{Difficulty, Game, Scene};
+use crate::high_score::HighScore;
+use crate::input::Input;
+use crate::sound::AudioManager;
+use crate::sprite::Sprites;
+use std::time::Instant;
+
+// 強化学習の環境で選べる行動
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Noop,
+    Left,
+    Right,
+    Fire,
+    LeftFire,
+    RightFire,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::Noop,
+        Action::Left,
+        Action::Right,
+        Action::Fire,
+        Action::LeftFire,
+        Action::RightFire,
+    ];
+    // 外部から操作するときの名前
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Noop => "noop",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Fire => "fire",
+            Action::LeftFire => "left_fire",
+            Action::RightFire => "right_fire",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+    pub fn to_input(self) -> Input {
+        let (left, right, fire) = match self {
+            Action::Noop => (false, false, false),
+            Action::Left => (true, false, false),
+            Action::Right => (false, true, false),
+            Action::Fire => (false, false, true),
+            Action::LeftFire => (true, false, true),
+            Action::RightFire => (false, true, true),
+        };
+        Input {
+            left,
+            right,
+            fire,
+            ..Default::default()
+        }
+    }
+    // 左右同時押しは移動しないので、左右どちらも押していないものとして扱う
+    pub fn from_input(input: &Input) -> Self {
+        match (
+            input.left && !input.right,
+            input.right && !input.left,
+            input.fire,
+        ) {
+            (true, _, false) => Action::Left,
+            (_, true, false) => Action::Right,
+            (true, _, true) => Action::LeftFire,
+            (_, true, true) => Action::RightFire,
+            (_, _, true) => Action::Fire,
+            _ => Action::Noop,
+        }
+    }
+}
+
+// ゲーム画面のドットを1ドット1バイト(0か1)で並べたもの
+pub struct Observation {
+    pub width: i32,
+    pub height: i32,
+    pub dots: Vec<u8>, // 上の行から順に左から右へ並べる
+}
+
+impl Observation {
+    // 1行ずつ「#」と「.」で表した文字列にする
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity(self.dots.len() + self.height as usize);
+        for row in self.dots.chunks(self.width as usize) {
+            text.extend(row.iter().map(|&dot| if dot == 0 { '.' } else { '#' }));
+            text.push('\n');
+        }
+        text
+    }
+}
+
+// 強化学習向けに、ウィンドウを使わずに1フレームずつゲームを進める環境
+pub struct Env {
+    game: Game,
+    difficulty: Difficulty,
+    downsample: i32, // 観測を縦横何分の1に縮小するか
+    score: i32,      // 前回の行動までの得点
+    audio: AudioManager,
+    sprites: Sprites,
+}
+
+impl Env {
+    // downsampleが2以上なら、その大きさの正方形ごとに1つでもドットがあれば1とする観測にする
+    pub fn new(difficulty: Difficulty, downsample: i32) -> Result<Self, AssetError> {
+        let sprites = Sprites::load()?;
+        Ok(Env {
+            game: Game::new(&sprites, HighScore::default(), difficulty),
+            difficulty,
+            downsample: downsample.max(1),
+            score: 0,
+            audio: AudioManager::silent(),
+            sprites,
+        })
+    }
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+    // 新しいゲームを始めて最初の観測を返す
+    pub fn reset(&mut self) -> Observation {
+        self.game = Game::new(&self.sprites, HighScore::default(), self.difficulty);
+        self.game.start();
+        self.score = 0;
+        self.observe()
+    }
+    // 1フレーム進めて、観測、得点の増分、ゲームが終わったかを返す
+    pub fn step(&mut self, action: Action) -> (Observation, i32, bool) {
+        self.game.update(&action.to_input());
+        // 効果音は鳴らさない
+        for request in self.game.take_sound_requests() {
+            self.audio.request(request);
+        }
+        let score = self.game.player_bullet.score;
+        let reward = score - self.score;
+        self.score = score;
+        let done = matches!(
+            self.game.scene,
+            Scene::Gameover(_) | Scene::EnterName | Scene::Title
+        );
+        (self.observe(), reward, done)
+    }
+    fn observe(&self) -> Observation {
+        let map = &self.game.map;
+        let n = self.downsample;
+        let width = (map.width() + n - 1) / n;
+        let height = (map.height() + n - 1) / n;
+        let mut dots = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let set = (y * n..(y * n + n).min(map.height()))
+                    .any(|dy| (x * n..(x * n + n).min(map.width())).any(|dx| map.dot(dx, dy)));
+                dots.push(set as u8);
+            }
+        }
+        Observation {
+            width,
+            height,
+            dots,
+        }
+    }
+}
+
+// ウィンドウを開かずに参考実装のボットを指定回数遊ばせ、各回の得点と速度を表示する
+// last_frameが真ならゲームが終わったときの観測も表示する
+pub fn run_bot(
+    episodes: u32,
+    difficulty: Difficulty,
+    downsample: i32,
+    last_frame: bool,
+) -> Result<(), AssetError> {
+    let mut env = Env::new(difficulty, downsample)?;
+    for episode in 1..=episodes {
+        let observation = env.reset();
+        if episode == 1 {
+            println!("観測の大きさ {}x{}", observation.width, observation.height);
+        }
+        let mut bot = ReferenceBot::default();
+        let mut total = 0;
+        let mut frames = 0;
+        let start = Instant::now();
+        let last = loop {
+            let action = Action::from_input(&bot.input(&GameView::new(env.game())));
+            let (observation, reward, done) = env.step(action);
+            total += reward;
+            frames += 1;
+            if done {
+                break observation;
+            }
+        };
+        let seconds = start.elapsed().as_secs_f64();
+        if last_frame {
+            print!("{}", last.to_text());
+        }
+        println!(
+            "{}回目: 得点 {} フレーム数 {} ({:.0}フレーム/秒)",
+            episode,
+            total,
+            frames,
+            frames as f64 / seconds
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reset_observes_the_whole_game_plane() {
+        let mut env = Env::new(Difficulty::Normal, 1).unwrap();
+        let observation = env.reset();
+        assert_eq!((observation.width, observation.height), (224, 208));
+        assert_eq!(observation.dots.len(), 224 * 208);
+    }
+
+    #[test]
+    fn downsample_sets_a_cell_for_any_dot_in_its_block() {
+        let mut env = Env::new(Difficulty::Normal, 4).unwrap();
+        let observation = env.reset();
+        assert_eq!((observation.width, observation.height), (56, 52));
+        // 4x4の区画のどのドットでも、その区画が1になる
+        for (dx, dy) in [(0, 0), (3, 0), (0, 3), (3, 3), (1, 2)] {
+            env.game.map.all_clear();
+            let (x, y) = (4 * 10 + dx, 4 * 7 + dy);
+            env.game.map.map[y / 8][x] |= 1 << (y % 8);
+            let observation = env.observe();
+            let set: Vec<usize> = (0..observation.dots.len())
+                .filter(|&i| observation.dots[i] == 1)
+                .collect();
+            assert_eq!(set, [7 * 56 + 10], "{:?}", (dx, dy));
+        }
+    }
+
+    #[test]
+    fn reward_is_the_score_gained() {
+        let mut env = Env::new(Difficulty::Normal, 1).unwrap();
+        env.reset();
+        let mut total = 0;
+        // 右に動きながら撃ち続ければエイリアンに当たる
+        for _ in 0..60 * 60 {
+            let score = env.game().player_bullet.score;
+            let (_, reward, _) = env.step(Action::RightFire);
+            assert_eq!(reward, env.game().player_bullet.score - score);
+            total += reward;
+            if 0 < reward {
+                break;
+            }
+        }
+        assert!(0 < total);
+        assert_eq!(total, env.game().player_bullet.score);
+    }
+
+    #[test]
+    fn done_at_gameover() {
+        let mut env = Env::new(Difficulty::Normal, 1).unwrap();
+        env.reset();
+        // 1機やられたら終わるようにする
+        env.game.player.start_life = 1;
+        env.game.player.life = 1;
+        for _ in 0..60 * 60 * 10 {
+            let (_, _, done) = env.step(Action::Noop);
+            let gameover = matches!(env.game().scene, Scene::Gameover(_));
+            assert_eq!(done, gameover, "{:?}", env.game().scene);
+            if done {
+                return;
+            }
+        }
+        panic!("何もしないプレイヤーがゲームオーバーにならない");
+    }
+}
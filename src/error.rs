@@ -0,0 +1,87 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// 読み込んだファイルの中身が不正なときのエラー
+pub fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// スプライトのドットデータの形の不正
+#[derive(Debug)]
+pub enum ShapeError {
+    // 高さが8または16でない
+    BadHeight(i32),
+    // 指定されたサイズと実際のデータのサイズが異なる
+    HeightMismatch { expected: i32, actual: i32 },
+    WidthMismatch { expected: i32, actual: i32 },
+    // 幅が異なる行がある
+    RaggedRows { row: usize },
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShapeError::BadHeight(height) => {
+                write!(f, "高さは8または16でなければなりません。(高さ: {})", height)
+            }
+            ShapeError::HeightMismatch { expected, actual } => write!(
+                f,
+                "高さは{}ドットのはずですが、実際のデータは{}ドットです。",
+                expected, actual
+            ),
+            ShapeError::WidthMismatch { expected, actual } => write!(
+                f,
+                "幅は{}ドットのはずですが、実際のデータは{}ドットです。",
+                expected, actual
+            ),
+            ShapeError::RaggedRows { row } => {
+                write!(f, "{}行目の幅が1行目と異なります。", row + 1)
+            }
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+// 画像、音声などの読み込みの失敗
+#[derive(Debug)]
+pub enum AssetError {
+    // 組み込みのデータにもファイルにも無いスプライト
+    UnknownSprite(String),
+    // ドットデータの形が不正なスプライト
+    InvalidSprite { name: String, source: ShapeError },
+    // 読み込めなかったスプライトのファイル
+    SpriteFile { path: String, source: io::Error },
+    // 読み込めなかった音声ファイル
+    MissingAudio { path: String, reason: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::UnknownSprite(name) => {
+                write!(f, "{}というスプライトはありません。", name)
+            }
+            AssetError::InvalidSprite { name, source } => {
+                write!(f, "スプライト{}が不正です: {}", name, source)
+            }
+            AssetError::SpriteFile { path, source } => {
+                write!(f, "{}を読み込めませんでした: {}", path, source)
+            }
+            AssetError::MissingAudio { path, reason } => {
+                write!(f, "音声ファイル{}を読み込めませんでした: {}", path, reason)
+            }
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::InvalidSprite { source, .. } => Some(source),
+            AssetError::SpriteFile { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,730 @@
+use crate::alien::{Alien, BulletManage};
+use crate::array_sprite::{array_sprite, array_string};
+use crate::canvas;
+use crate::commander::Commander;
+use crate::dot_map::DotMap;
+use crate::high_score::{Entry, HighScore, NameEntry, NAME_LEN};
+use crate::input::Input;
+use crate::player::{Bullet, Player};
+use crate::sound::{SoundQueue, SoundRequest};
+use crate::sprite::Sprites;
+use crate::ufo::Ufo;
+use macroquad::prelude::IVec2;
+use std::ops::Range;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Scene {
+    Title,
+    SelectPlayers(usize), // 選択中のPLAY_MODESの項目
+    Play,
+    Pause,
+    LaunchGame(i32),
+    LaunchStage(i32),
+    PlayPlayer(i32), // 交互プレイの交代時
+    ResetStage,
+    Gameover(i32),
+    EnterName,
+}
+
+// 人数選択の項目(表示名、キャノンを操作する人数、協力プレイなら真、対戦プレイなら真)
+const PLAY_MODES: [(&str, usize, bool, bool); 4] = [
+    ("1 PLAYER", 1, false, false),
+    ("2 PLAYERS", 2, false, false),
+    ("2 PLAYERS CO-OP", 2, true, false),
+    ("2 PLAYERS VS", 1, false, true),
+];
+// 交代時に「PLAY PLAYER<n>」を表示するカウント数
+const PLAY_PLAYER_CNT: i32 = 120;
+// シールドを置くドットマップの行
+const SHIELD_ROWS: std::ops::Range<usize> = 20..22;
+
+// 難易度(アーケード版のディップスイッチ相当)
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name() == name)
+    }
+    // 次の難易度(Hardの次はEasyに戻る)
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+    // ゲーム開始時の残機
+    fn start_life(self) -> i32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 2,
+        }
+    }
+    // 残機が1増える得点
+    fn bonus_score(self) -> Option<i32> {
+        match self {
+            Difficulty::Easy => Some(1000),
+            Difficulty::Normal => Some(1500),
+            Difficulty::Hard => None,
+        }
+    }
+}
+
+// 交互プレイで交代を待っているプレイヤーの状態
+struct PlayerState {
+    player: Player,
+    player_bullet: Bullet,
+    alien: Alien,
+    stage: usize,
+    shields: Option<Vec<Vec<u8>>>, // シールドの行(ステージ開始前ならNone)
+    over: bool,                    // ゲームオーバー済なら真
+}
+
+// 協力プレイの2人目のキャノンとその弾
+pub struct Partner {
+    pub player: Player,
+    pub bullet: Bullet,
+}
+
+// ウィンドウ、入力デバイス、音声出力に依存しないゲーム本体
+pub struct Game {
+    pub scene: Scene,
+    pub map: DotMap,
+    pub player: Player,
+    pub player_bullet: Bullet,
+    pub ufo: Ufo,
+    pub alien: Alien,
+    pub alien_bullets: BulletManage,
+    shield: Vec<u8>,
+    shield_width: usize,
+    // ステージの面数
+    pub stage: usize,
+    // 真の場合、画面全体を赤色にする
+    pub player_exploding: bool,
+    pub high_score: HighScore,
+    // キャノンを操作するプレイ人数(1または2)
+    pub players: usize,
+    // 2人が同時に遊ぶ協力プレイなら真(偽なら2人は交互に遊ぶ)
+    pub coop: bool,
+    // 2人目がエイリアン側を操作する対戦プレイなら真
+    pub versus: bool,
+    // 協力プレイの2人目
+    pub partner: Option<Partner>,
+    // 対戦プレイでエイリアン側を操作する2人目
+    pub commander: Option<Commander>,
+    // プレイ中のプレイヤー(0または1)
+    pub current: usize,
+    // 交互プレイで交代を待っているプレイヤー
+    waiting: Option<PlayerState>,
+    // 交代後に描き直すシールドの行(ステージ開始前ならNone)
+    restore_shields: Option<Vec<Vec<u8>>>,
+    // 人数選択での前回の入力
+    pre_input: Input,
+    name_entry: NameEntry,
+    // イニシャルを入力中のプレイヤー
+    entry_player: usize,
+    // 0〜9、A〜Zの文字のスプライト
+    font: Vec<Vec<u8>>,
+    // 再生要求された効果音
+    se: SoundQueue,
+}
+
+impl Game {
+    pub fn new(sprites: &Sprites, high_score: HighScore, difficulty: Difficulty) -> Self {
+        let sprites = sprites.clone();
+        let [explosion_1, explosion_2] = sprites.player_explosion;
+        let mut player = Player::new(sprites.player, explosion_1, explosion_2);
+        player.start_life = difficulty.start_life();
+        player.life = player.start_life;
+        player.bonus_score = difficulty.bonus_score();
+
+        Game {
+            // 起動直後はタイトル画面から始める
+            scene: Scene::Title,
+            map: DotMap::game(),
+            player,
+            player_bullet: Bullet::new(sprites.player_bullet, sprites.player_bullet_explosion),
+            ufo: Ufo::new(sprites.ufo, sprites.ufo_explosion, sprites.font.clone()),
+            alien: Alien::new(
+                sprites.octopus[0].clone(),
+                sprites.octopus[1].clone(),
+                sprites.crab[0].clone(),
+                sprites.crab[1].clone(),
+                sprites.squid[0].clone(),
+                sprites.squid[1].clone(),
+                sprites.alien_explosion,
+            ),
+            alien_bullets: BulletManage::new(sprites.alien_bullet_explosion),
+            shield: sprites.shield,
+            shield_width: sprites.shield_width,
+            stage: 1,
+            player_exploding: false,
+            high_score,
+            players: 1,
+            coop: false,
+            versus: false,
+            partner: None,
+            commander: None,
+            current: 0,
+            waiting: None,
+            restore_shields: None,
+            pre_input: Input::default(),
+            name_entry: NameEntry::new(),
+            entry_player: 0,
+            font: sprites.font,
+            se: SoundQueue::default(),
+        }
+    }
+    // タイトル画面からプレイ人数の選択に進む
+    pub fn select_players(&mut self) {
+        self.scene = Scene::SelectPlayers(0);
+        // タイトル画面で押した決定キーで確定しないようにする
+        self.pre_input = Input {
+            fire: true,
+            ..Default::default()
+        };
+        self.draw_select_players(0);
+    }
+    // プレイヤーごとの獲得得点
+    pub fn scores(&self) -> [i32; 2] {
+        let mut scores = [0; 2];
+        scores[self.current] = self.player_bullet.score;
+        if let Some(waiting) = &self.waiting {
+            scores[1 - self.current] = waiting.player_bullet.score;
+        }
+        if let Some(partner) = &self.partner {
+            scores[1] = partner.bullet.score;
+        }
+        scores
+    }
+    // 協力プレイの2人目のキャノンの範囲(文字単位の行、ドット単位のX座標の範囲)
+    pub fn partner_area(&self) -> Option<(usize, Range<i32>)> {
+        self.partner
+            .as_ref()
+            .filter(|p| 0 < p.player.life)
+            .map(|p| {
+                let pos = p.player.pos;
+                ((pos.y / 8) as usize, pos.x..pos.x + p.player.width())
+            })
+    }
+    // タイトル画面からゲームを開始する
+    pub fn start(&mut self) {
+        self.scene = Scene::LaunchGame(10);
+        // 前回のドットマップをすべて消す
+        self.map.all_clear();
+    }
+    // 前回呼び出し時から溜まった効果音の再生要求を取り出す
+    pub fn take_sound_requests(&mut self) -> Vec<SoundRequest> {
+        self.se.drain()
+    }
+    // 1フレーム分ゲームを進める
+    pub fn update(&mut self, input: &Input) {
+        match self.scene {
+            Scene::Title => {}
+            Scene::SelectPlayers(cursor) => {
+                // 左右で項目を選び、射撃ボタンで確定する(押された瞬間のみ有効)
+                let pre = self.pre_input;
+                let cursor = if input.left && !pre.left {
+                    cursor.saturating_sub(1)
+                } else if input.right && !pre.right {
+                    (cursor + 1).min(PLAY_MODES.len() - 1)
+                } else {
+                    cursor
+                };
+                if input.fire && !pre.fire {
+                    (_, self.players, self.coop, self.versus) = PLAY_MODES[cursor];
+                    self.start();
+                } else {
+                    self.scene = Scene::SelectPlayers(cursor);
+                    self.draw_select_players(cursor);
+                }
+                self.pre_input = *input;
+            }
+            Scene::Play => {
+                // Escキーが押されていたらポーズ
+                if input.pause {
+                    self.scene = Scene::Pause;
+                }
+                // 協力プレイでは2人の射撃数と得点を合わせて扱う
+                let (partner_fire_cnt, partner_score) = self
+                    .partner
+                    .as_ref()
+                    .map_or((0, 0), |p| (p.bullet.fire_cnt, p.bullet.score));
+                // 更新処理
+                self.ufo.update(
+                    &mut self.map,
+                    self.player_bullet.fire_cnt + partner_fire_cnt,
+                    self.alien.live_num,
+                    &mut self.se,
+                );
+
+                self.alien
+                    .update(&mut self.map, self.player_exploding, &mut self.se);
+                if let Some(commander) = self.commander.as_mut() {
+                    commander.update(input, &self.alien, &mut self.alien_bullets, &mut self.ufo);
+                }
+                let mut players = vec![&mut self.player];
+                if let Some(partner) = self.partner.as_mut() {
+                    players.push(&mut partner.player);
+                }
+                // 残機の無くなったプレイヤーは狙わない
+                players.retain(|player| 0 < player.life);
+                self.alien_bullets.update(
+                    &mut self.map,
+                    &mut players,
+                    &self.alien,
+                    self.player_bullet.score + partner_score,
+                    &mut self.se,
+                );
+
+                let exploding = self.player.explosion_cnt.is_some();
+                let mut player_input = *input;
+                if let Some(partner) = &self.partner {
+                    block_partner(&mut player_input, &self.player, &partner.player);
+                }
+                if 0 < self.player.life {
+                    self.player.update(&mut self.map, &player_input);
+                    self.player_bullet.update(
+                        &mut self.map,
+                        &mut self.player,
+                        &mut self.ufo,
+                        &mut self.alien,
+                        &player_input,
+                        &mut self.se,
+                    );
+                }
+                if let Some(partner) = self.partner.as_mut() {
+                    let mut partner_input = input.partner();
+                    block_partner(&mut partner_input, &partner.player, &self.player);
+                    if 0 < partner.player.life {
+                        partner.player.update(&mut self.map, &partner_input);
+                        partner.bullet.update(
+                            &mut self.map,
+                            &mut partner.player,
+                            &mut self.ufo,
+                            &mut self.alien,
+                            &partner_input,
+                            &mut self.se,
+                        );
+                    }
+                }
+
+                // エイリアンが全滅したら
+                if self.alien.live_num <= 0 {
+                    // 次のステージへ進む
+                    self.scene = Scene::LaunchStage(120);
+                }
+                // 全員の残機が0またはエイリアンがプレイヤーの高さまで侵攻したら
+                let partner_out = self.partner.as_ref().is_none_or(|p| p.player.life <= 0);
+                if self.player.life <= 0 && partner_out || self.alien.invaded() {
+                    // ゲームオーバー
+                    self.scene = Scene::Gameover(120);
+                    // 音を止める
+                    self.ufo.reset(&mut self.se);
+                    if self.alien.invaded() {
+                        // プレイヤーの高さに降りてきた個体を描く
+                        self.alien
+                            .update(&mut self.map, self.player_exploding, &mut self.se);
+                        // エイリアンに侵攻されていたら爆発を起こす
+                        self.player.remove(&mut self.map, &mut self.se);
+                        if let Some(partner) = self.partner.as_mut() {
+                            if 0 < partner.player.life {
+                                partner.player.remove(&mut self.map, &mut self.se);
+                            }
+                        }
+                    };
+                }
+                // プレイヤーが爆発中は画面全体を赤にする
+                self.player_exploding = self.player.explosion_cnt.is_some()
+                    || self
+                        .partner
+                        .as_ref()
+                        .is_some_and(|p| p.player.explosion_cnt.is_some());
+                // 交互プレイでは撃破されたら(再出撃の代わりに)もう一人に交代する
+                let respawned = exploding && self.player.explosion_cnt.is_none();
+                if self.scene == Scene::Play && respawned && self.waiting_in_game() {
+                    self.swap_player(false);
+                }
+            }
+            Scene::ResetStage => {
+                // ゲーム開始、ステージ開始時共通
+                self.scene = Scene::Play;
+                self.draw_stage_base();
+                // シールド配置
+                for i in 0..4 {
+                    let gap = (self.shield_width + 23) * i;
+                    for dx in 0..self.shield_width {
+                        self.map.map[20][gap + 33 + dx] = self.shield[dx];
+                        self.map.map[21][gap + 33 + dx] = self.shield[self.shield_width + dx];
+                    }
+                }
+                self.alien.reset(self.stage, &mut self.se);
+                self.alien_bullets.reset();
+                self.ufo.reset(&mut self.se);
+            }
+            Scene::LaunchGame(cnt) => {
+                // 一定時間経過したらゲーム開始
+                if cnt < 0 {
+                    self.scene = Scene::ResetStage;
+
+                    self.stage = 1;
+                    self.current = 0;
+                    self.player.reset_all();
+                    self.player_bullet.reset_all();
+                    self.waiting = None;
+                    self.partner = None;
+                    // 対戦プレイでは2人目が撃たせる列とUFOの発進を決める
+                    self.commander = self.versus.then(Commander::new);
+                    self.alien_bullets.commanded = self.versus;
+                    self.ufo.commanded = self.versus;
+                    if self.coop {
+                        // 2人目は右端から出撃する
+                        let mut player = self.player.clone();
+                        player.home_x = canvas::GAME_WIDTH - 8 - player.width();
+                        player.reset_all();
+                        self.partner = Some(Partner {
+                            player,
+                            bullet: self.player_bullet.clone(),
+                        });
+                    } else if self.players == 2 {
+                        // 2人目は同じ状態から始める
+                        self.waiting = Some(PlayerState {
+                            player: self.player.clone(),
+                            player_bullet: self.player_bullet.clone(),
+                            alien: self.alien.clone(),
+                            stage: 1,
+                            shields: None,
+                            over: false,
+                        });
+                        self.restore_shields = None;
+                        self.show_play_player();
+                    }
+                } else {
+                    self.scene = Scene::LaunchGame(cnt - 1);
+                }
+            }
+            Scene::LaunchStage(cnt) => {
+                // 一定時間経過したら次のステージ開始
+                if cnt < 0 {
+                    self.scene = Scene::ResetStage;
+
+                    self.stage += 1;
+                    self.reset_players_stage();
+                } else {
+                    self.scene = Scene::LaunchStage(cnt - 1);
+                }
+            }
+            Scene::PlayPlayer(cnt) => {
+                if 0 <= cnt {
+                    self.scene = Scene::PlayPlayer(cnt - 1);
+                } else if let Some(shields) = self.restore_shields.take() {
+                    // 交代前の画面を描き直して続きから再開する
+                    self.scene = Scene::Play;
+                    self.draw_stage_base();
+                    for (row, dots) in SHIELD_ROWS.zip(shields) {
+                        self.map.map[row] = dots;
+                    }
+                    self.alien.draw_all(&mut self.map);
+                } else {
+                    // まだ遊んでいないプレイヤーはステージの最初から始める
+                    self.scene = Scene::ResetStage;
+                }
+            }
+            Scene::Gameover(cnt) => {
+                // 一定時間経過したらタイトル画面に戻る
+                if cnt < 0 {
+                    if self.waiting_in_game() {
+                        // 交互プレイではもう一人のプレイヤーに交代する
+                        self.swap_player(true);
+                    } else {
+                        self.begin_name_entry(0);
+                    }
+                } else {
+                    self.scene = Scene::Gameover(cnt - 1);
+                    // プレイヤーを爆発させる
+                    let partner = self.partner.as_mut().map(|p| &mut p.player);
+                    for player in std::iter::once(&mut self.player).chain(partner) {
+                        if let Some(cnt) = player.explosion_cnt {
+                            if cnt <= player.const_max_explosion_cnt {
+                                player.update(&mut self.map, input);
+                            }
+                        }
+                    }
+                }
+            }
+            Scene::Pause => {
+                if input.restart {
+                    // 得点と残機はそのままでステージを最初からやり直す
+                    self.scene = Scene::ResetStage;
+                    self.reset_players_stage();
+                    self.player_exploding = false;
+                } else if input.pause {
+                    // Escキーが押されていたらポーズ解除
+                    self.scene = Scene::Play;
+                }
+            }
+            Scene::EnterName => {
+                if let Some(name) = self.name_entry.update(input) {
+                    // 入力が終わったらハイスコア表に登録して次のプレイヤーの入力に進む
+                    self.high_score.insert(Entry {
+                        name,
+                        score: self.scores()[self.entry_player],
+                    });
+                    self.begin_name_entry(self.entry_player + 1);
+                } else {
+                    self.draw_name_entry();
+                }
+            }
+        }
+    }
+    // 場にいるプレイヤーとその弾をステージ開始時の状態にする
+    fn reset_players_stage(&mut self) {
+        self.player.reset_stage();
+        self.player_bullet.reset_stage();
+        if let Some(partner) = self.partner.as_mut() {
+            partner.player.reset_stage();
+            partner.bullet.reset_stage();
+        }
+    }
+    // 交代を待っているプレイヤーがまだゲームオーバーになっていなければ真
+    fn waiting_in_game(&self) -> bool {
+        self.waiting.as_ref().is_some_and(|waiting| !waiting.over)
+    }
+    // 交代を待っているプレイヤーと入れ替える
+    // overが真なら交代前のプレイヤーはゲームオーバーとする
+    fn swap_player(&mut self, over: bool) {
+        let Some(mut waiting) = self.waiting.take() else {
+            return;
+        };
+        let shields = self.map.map[SHIELD_ROWS].to_vec();
+        std::mem::swap(&mut self.player, &mut waiting.player);
+        std::mem::swap(&mut self.player_bullet, &mut waiting.player_bullet);
+        std::mem::swap(&mut self.alien, &mut waiting.alien);
+        std::mem::swap(&mut self.stage, &mut waiting.stage);
+        self.restore_shields = waiting.shields.replace(shields);
+        waiting.over = over;
+        self.waiting = Some(waiting);
+        self.current = 1 - self.current;
+        // 画面上の弾とUFOは引き継がない
+        self.player.reset_stage();
+        self.player_bullet.resume();
+        self.alien_bullets.reset();
+        self.ufo.reset(&mut self.se);
+        self.player_exploding = false;
+        self.show_play_player();
+    }
+    // 「PLAY PLAYER<n>」を表示する
+    fn show_play_player(&mut self) {
+        self.scene = Scene::PlayPlayer(PLAY_PLAYER_CNT);
+        self.map.all_clear();
+        let text = format!("PLAY PLAYER<{}>", self.current + 1);
+        array_string(&mut self.map.map, IVec2::new(56, 88), &text, &self.font);
+    }
+    // ステージの背景(プレイヤーの下の横線)だけを描く
+    fn draw_stage_base(&mut self) {
+        // すべて消す
+        self.map.all_clear();
+        // プレイヤーの下の横線
+        self.map.draw_holizon_line(canvas::GAME_HEIGHT - 1);
+    }
+    fn draw_select_players(&mut self, cursor: usize) {
+        self.map.all_clear();
+        array_string(
+            &mut self.map.map,
+            IVec2::new(56, 64),
+            "SELECT PLAYERS",
+            &self.font,
+        );
+        for (i, (label, _, _, _)) in PLAY_MODES.iter().enumerate() {
+            let y = 96 + 16 * i as i32;
+            if i == cursor {
+                array_string(&mut self.map.map, IVec2::new(40, y), ">", &self.font);
+            }
+            array_string(&mut self.map.map, IVec2::new(56, y), label, &self.font);
+        }
+    }
+    // from番目以降のプレイヤーでハイスコア表に載る得点があればイニシャルを入力させる
+    // 無ければタイトル画面に戻る
+    fn begin_name_entry(&mut self, from: usize) {
+        let scores = self.scores();
+        let Some(i) = (from..self.players).find(|&i| self.high_score.is_high_score(scores[i]))
+        else {
+            self.scene = Scene::Title;
+            self.player_bullet.score = 0;
+            self.waiting = None;
+            self.partner = None;
+            self.commander = None;
+            self.current = 0;
+            return;
+        };
+        self.entry_player = i;
+        self.scene = Scene::EnterName;
+        self.name_entry = NameEntry::new();
+        self.map.all_clear();
+        if self.players == 2 {
+            let text = format!("PLAYER<{}>", i + 1);
+            array_string(&mut self.map.map, IVec2::new(76, 32), &text, &self.font);
+        }
+        array_string(
+            &mut self.map.map,
+            IVec2::new(72, 56),
+            "HIGH SCORE",
+            &self.font,
+        );
+        array_string(
+            &mut self.map.map,
+            IVec2::new(36, 80),
+            "ENTER YOUR INITIALS",
+            &self.font,
+        );
+        self.draw_name_entry();
+    }
+    // 入力中のイニシャルと、入力中の文字の下線を描く
+    fn draw_name_entry(&mut self) {
+        let pos = IVec2::new(100, 104);
+        array_string(
+            &mut self.map.map,
+            pos,
+            &self.name_entry.name_string(),
+            &self.font,
+        );
+        for i in 0..NAME_LEN {
+            let underline: &[u8] = if i == self.name_entry.cursor {
+                &[0, 2, 2, 2, 2, 2, 0, 0]
+            } else {
+                &[0; 8]
+            };
+            array_sprite(
+                &mut self.map.map,
+                IVec2::new(pos.x + 8 * i as i32, pos.y + 8),
+                underline,
+            );
+        }
+    }
+}
+
+// 協力プレイで相方のキャノンに重ならないよう、相方に向かう移動を止める
+fn block_partner(input: &mut Input, player: &Player, partner: &Player) {
+    if partner.life <= 0 {
+        return;
+    }
+    let gap = partner.pos.x - player.pos.x;
+    if 0 < gap && gap <= player.width() {
+        input.right = false;
+    }
+    if gap < 0 && -gap <= partner.width() {
+        input.left = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::{Se, SoundRequest};
+
+    fn fire() -> Input {
+        Input {
+            fire: true,
+            ..Default::default()
+        }
+    }
+
+    fn new_game() -> Game {
+        let sprites = Sprites::load().expect("組み込みのスプライトで作れる");
+        Game::new(&sprites, HighScore::default(), Difficulty::Normal)
+    }
+
+    // 同じ入力でframesフレーム進め、通った場面と効果音の再生要求を返す
+    fn run(game: &mut Game, input: Input, frames: usize) -> (Vec<Scene>, Vec<SoundRequest>) {
+        let mut scenes = Vec::new();
+        let mut sounds = Vec::new();
+        for _ in 0..frames {
+            game.update(&input);
+            if scenes.last() != Some(&game.scene) {
+                scenes.push(game.scene);
+            }
+            sounds.extend(game.take_sound_requests());
+        }
+        (scenes, sounds)
+    }
+
+    #[test]
+    fn start_leads_to_play() {
+        let mut game = new_game();
+        game.start();
+        let (scenes, _) = run(&mut game, Input::default(), 20);
+        assert!(matches!(scenes[0], Scene::LaunchGame(_)));
+        assert!(scenes.contains(&Scene::ResetStage));
+        assert_eq!(game.scene, Scene::Play);
+        assert_eq!(game.player.life, game.player.start_life);
+    }
+
+    #[test]
+    fn select_players_needs_a_new_fire_press() {
+        let mut game = new_game();
+        game.select_players();
+        // タイトル画面から押しっぱなしの射撃ボタンでは確定しない
+        run(&mut game, fire(), 5);
+        assert_eq!(game.scene, Scene::SelectPlayers(0));
+        run(&mut game, Input::default(), 1);
+        game.update(&fire());
+        assert!(matches!(game.scene, Scene::LaunchGame(_)));
+        assert_eq!((game.players, game.coop, game.versus), (1, false, false));
+    }
+
+    #[test]
+    fn fire_emits_shoot_after_the_ban() {
+        let mut game = new_game();
+        game.start();
+        run(&mut game, Input::default(), 20);
+        // ステージ開始直後は射撃禁止
+        let (_, sounds) = run(&mut game, fire(), 10);
+        assert!(!sounds.contains(&SoundRequest::Play(Se::Shoot)));
+        let (_, sounds) = run(&mut game, fire(), 100);
+        assert!(sounds.contains(&SoundRequest::Play(Se::Shoot)));
+        assert!(0 < game.player_bullet.fire_cnt);
+    }
+
+    #[test]
+    fn invader_steps_cycle_through_four_notes() {
+        let mut game = new_game();
+        game.start();
+        let (_, sounds) = run(&mut game, Input::default(), 300);
+        let steps: Vec<usize> = sounds
+            .iter()
+            .filter_map(|request| match request {
+                SoundRequest::Play(Se::InvaderStep(i)) => Some(*i),
+                _ => None,
+            })
+            .collect();
+        assert!(4 < steps.len());
+        for (n, i) in steps.into_iter().enumerate() {
+            assert_eq!(i, n % 4);
+        }
+    }
+
+    #[test]
+    fn idle_player_loses_the_game() {
+        let mut game = new_game();
+        game.start();
+        let (scenes, sounds) = run(&mut game, Input::default(), 60 * 60 * 10);
+        assert!(sounds.contains(&SoundRequest::Play(Se::PlayerExplosion)));
+        assert!(scenes
+            .iter()
+            .any(|scene| matches!(scene, Scene::Gameover(_))));
+        // 得点0ではイニシャル入力を飛ばしてタイトル画面に戻る
+        assert!(!scenes.contains(&Scene::EnterName));
+        assert_eq!(game.scene, Scene::Title);
+    }
+}
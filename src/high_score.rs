@@ -0,0 +1,280 @@
+use crate::input::Input;
+use std::fs;
+use std::io;
+
+// ハイスコア表の保存先
+pub const HIGH_SCORE_PATH: &str = "high_score.txt";
+// ハイスコア表に残す件数
+const MAX_ENTRY_NUM: usize = 10;
+// イニシャルの文字数
+pub const NAME_LEN: usize = 3;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Entry {
+    pub name: String, // イニシャル(A〜Zの3文字)
+    pub score: i32,
+}
+
+// 得点の高い順に並んだハイスコア表
+#[derive(Clone, Default)]
+pub struct HighScore {
+    pub entries: Vec<Entry>,
+}
+
+impl HighScore {
+    // ファイルが無い場合は空の表を返す
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(HighScore::default()),
+            Err(e) => return Err(e),
+        };
+        let mut high_score = HighScore::default();
+        // 1行に「イニシャル 得点」
+        // イニシャルがA〜ZのNAME_LEN文字でない行は、表示できないので読み飛ばす
+        for line in text.lines() {
+            let entry = line.split_once(' ').and_then(|(name, score)| {
+                if name.len() != NAME_LEN || !name.bytes().all(|b| b.is_ascii_uppercase()) {
+                    return None;
+                }
+                Some(Entry {
+                    name: name.to_owned(),
+                    score: score.trim().parse().ok()?,
+                })
+            });
+            match entry {
+                Some(entry) => high_score.insert(entry),
+                None => eprintln!("ハイスコア表の不正な行を読み飛ばしました: {}", line),
+            }
+        }
+        Ok(high_score)
+    }
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let text: String = self
+            .entries
+            .iter()
+            .map(|e| format!("{} {}\n", e.name, e.score))
+            .collect();
+        fs::write(path, text)
+    }
+    // 表の1位の得点(表が空なら0)
+    pub fn top_score(&self) -> i32 {
+        self.entries.first().map_or(0, |e| e.score)
+    }
+    // 指定した得点が表に載るなら真を返す
+    pub fn is_high_score(&self, score: i32) -> bool {
+        0 < score
+            && self
+                .entries
+                .get(MAX_ENTRY_NUM - 1)
+                .is_none_or(|e| e.score < score)
+    }
+    // 同点の場合は先に登録されたほうを上位にする
+    pub fn insert(&mut self, entry: Entry) {
+        let i = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(i, entry);
+        self.entries.truncate(MAX_ENTRY_NUM);
+    }
+}
+
+// ゲームオーバー後のイニシャル入力
+pub struct NameEntry {
+    name: [u8; NAME_LEN], // 各文字のA〜Zを0〜25で表す
+    pub cursor: usize,    // 入力中の文字の位置
+    repeat_cnt: i32,      // 左右キー押しっぱなしで次の文字に送るまでの残りカウント
+    pre_fire: bool,       // 前回の更新で射撃ボタンが押されていたら真
+}
+
+impl NameEntry {
+    pub fn new() -> Self {
+        NameEntry {
+            name: [0; NAME_LEN],
+            cursor: 0,
+            repeat_cnt: 0,
+            // ゲーム中から押しっぱなしの射撃ボタンで確定しないようにする
+            pre_fire: true,
+        }
+    }
+    // 左右で文字を選び、射撃ボタンで確定する
+    // 全文字確定したらイニシャルを返す
+    pub fn update(&mut self, input: &Input) -> Option<String> {
+        let dir = input.right as i32 - input.left as i32;
+        if dir == 0 {
+            self.repeat_cnt = 0;
+        } else {
+            if self.repeat_cnt <= 0 {
+                let c = &mut self.name[self.cursor];
+                *c = (*c as i32 + dir).rem_euclid(26) as u8;
+                self.repeat_cnt = 10;
+            }
+            self.repeat_cnt -= 1;
+        }
+        // 押された瞬間のみ確定する
+        if input.fire && !self.pre_fire {
+            self.cursor += 1;
+        }
+        self.pre_fire = input.fire;
+        if NAME_LEN <= self.cursor {
+            Some(self.name_string())
+        } else {
+            None
+        }
+    }
+    pub fn name_string(&self) -> String {
+        self.name.iter().map(|&c| (b'A' + c) as char).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_path;
+
+    fn entry(name: &str, score: i32) -> Entry {
+        Entry {
+            name: name.to_owned(),
+            score,
+        }
+    }
+    // 得点が10, 20, ..., 100の10件で埋まった表
+    fn full_table() -> HighScore {
+        let mut high_score = HighScore::default();
+        for i in 1..=MAX_ENTRY_NUM as i32 {
+            high_score.insert(entry("AAA", i * 10));
+        }
+        high_score
+    }
+
+    #[test]
+    fn insert_sorts_and_keeps_earlier_ties_above() {
+        let mut high_score = HighScore::default();
+        high_score.insert(entry("AAA", 100));
+        high_score.insert(entry("BBB", 300));
+        high_score.insert(entry("CCC", 100));
+        high_score.insert(entry("DDD", 200));
+        let names: Vec<&str> = high_score.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["BBB", "DDD", "AAA", "CCC"]);
+        assert_eq!(high_score.top_score(), 300);
+    }
+
+    #[test]
+    fn insert_truncates_the_table() {
+        let mut high_score = full_table();
+        high_score.insert(entry("ZZZ", 55));
+        assert_eq!(high_score.entries.len(), MAX_ENTRY_NUM);
+        assert_eq!(high_score.entries[5], entry("ZZZ", 55));
+        assert_eq!(high_score.entries.last().unwrap().score, 20);
+        // 最下位と同点では表に残らない
+        high_score.insert(entry("YYY", 20));
+        assert!(high_score.entries.iter().all(|e| e.name != "YYY"));
+    }
+
+    #[test]
+    fn is_high_score_needs_a_positive_score_above_the_last_entry() {
+        let empty = HighScore::default();
+        assert!(!empty.is_high_score(0));
+        assert!(empty.is_high_score(1));
+        assert_eq!(empty.top_score(), 0);
+
+        let mut high_score = full_table();
+        high_score.entries.pop();
+        // 9件なら10位に入れる
+        assert!(high_score.is_high_score(10));
+        assert!(!high_score.is_high_score(0));
+
+        let high_score = full_table();
+        assert!(!high_score.is_high_score(10));
+        assert!(high_score.is_high_score(11));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let path = temp_path("high-score-test.txt");
+        let mut high_score = full_table();
+        high_score.entries[0].name = "XYZ".to_owned();
+        high_score.save(&path).unwrap();
+        let loaded = HighScore::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries, high_score.entries);
+    }
+
+    #[test]
+    fn load_skips_bad_lines() {
+        let path = temp_path("high-score-bad.txt");
+        fs::write(
+            &path,
+            "ABC 100\nab1 90\nABCD 80\nXYZ many\nÀBC 70\nDEF 60\n",
+        )
+        .unwrap();
+        let loaded = HighScore::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.entries, [entry("ABC", 100), entry("DEF", 60)]);
+    }
+
+    #[test]
+    fn load_of_a_missing_file_is_empty() {
+        let loaded = HighScore::load(&temp_path("high-score-missing.txt")).unwrap();
+        assert!(loaded.entries.is_empty());
+    }
+
+    #[test]
+    fn name_entry_wraps_around_the_alphabet() {
+        let mut name_entry = NameEntry::new();
+        let left = Input {
+            left: true,
+            ..Input::default()
+        };
+        name_entry.update(&left);
+        assert_eq!(name_entry.name_string(), "ZAA");
+        let right = Input {
+            right: true,
+            ..Input::default()
+        };
+        name_entry.update(&Input::default());
+        name_entry.update(&right);
+        assert_eq!(name_entry.name_string(), "AAA");
+    }
+
+    #[test]
+    fn name_entry_repeats_while_held() {
+        let mut name_entry = NameEntry::new();
+        let right = Input {
+            right: true,
+            ..Input::default()
+        };
+        // 押した瞬間に1文字、以後10回ごとに1文字進む
+        for _ in 0..21 {
+            name_entry.update(&right);
+        }
+        assert_eq!(name_entry.name_string(), "DAA");
+        // 離すとすぐ次の文字に送れる
+        name_entry.update(&Input::default());
+        name_entry.update(&right);
+        assert_eq!(name_entry.name_string(), "EAA");
+    }
+
+    #[test]
+    fn name_entry_needs_a_new_fire_press_for_each_letter() {
+        let mut name_entry = NameEntry::new();
+        let fire = Input {
+            fire: true,
+            ..Input::default()
+        };
+        // ゲーム中から押しっぱなしの射撃ボタンでは確定しない
+        name_entry.update(&fire);
+        assert_eq!(name_entry.cursor, 0);
+        let mut result = None;
+        for _ in 0..NAME_LEN {
+            name_entry.update(&Input::default());
+            name_entry.update(&fire);
+            // 押しっぱなしでは次の文字に進まない
+            result = name_entry.update(&fire);
+        }
+        assert_eq!(name_entry.cursor, NAME_LEN);
+        assert_eq!(result.as_deref(), Some("AAA"));
+    }
+}
@@ -0,0 +1,42 @@
+use crate::controls::{Action, Controls};
+
+// 1フレーム分のプレイヤーの入力
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Input {
+    pub left: bool,    // 左移動
+    pub right: bool,   // 右移動
+    pub fire: bool,    // 射撃
+    pub pause: bool,   // ポーズ切り替え(押された瞬間のみ真)
+    pub restart: bool, // ポーズ中のステージやり直し(選ばれた瞬間のみ真)
+    pub left2: bool,   // 2人目の左移動
+    pub right2: bool,  // 2人目の右移動
+    pub fire2: bool,   // 2人目の射撃
+    pub ufo2: bool,    // 対戦プレイの2人目のUFO発進
+}
+
+impl Input {
+    // キーボードの状態から割り当てに従って入力を作る
+    pub fn from_keyboard(controls: &Controls) -> Self {
+        Input {
+            left: controls.is_down(Action::Left),
+            right: controls.is_down(Action::Right),
+            fire: controls.is_down(Action::Fire),
+            pause: controls.is_pressed(Action::Pause),
+            // ポーズメニューから設定する
+            restart: false,
+            left2: controls.is_down(Action::Left2),
+            right2: controls.is_down(Action::Right2),
+            fire2: controls.is_down(Action::Fire2),
+            ufo2: controls.is_down(Action::Ufo2),
+        }
+    }
+    // 協力プレイの2人目の移動と射撃を1人目の入力の形にする
+    pub fn partner(&self) -> Input {
+        Input {
+            left: self.left2,
+            right: self.right2,
+            fire: self.fire2,
+            ..Default::default()
+        }
+    }
+}
@@ -1,348 +1,608 @@
-use alien::Alien;
-use dot_map::DotMap;
-use macroquad::{
-    audio::{load_sound, Sound},
-    prelude::*,
-};
-use pause::draw_pause;
-use player::{Bullet, Player};
+use attract::{Attract, Page};
+use bot::{Bot, GameView, ReferenceBot};
+use controls::ControlsScreen;
+use dot_map::{ColorArea, DotMap};
+use game::{Difficulty, Game, Scene};
+use high_score::HighScore;
+use input::Input;
+use macroquad::prelude::*;
+use palette::Palette;
+use pause::{PauseAction, PauseMenu};
+use renderer::Renderer;
+use replay::{Playback, Replay};
+use settings::Settings;
+use sound::{AudioManager, MacroquadBackend};
+use sprite::Sprites;
 use std::error::Error;
-use ufo::Ufo;
+use synth::SynthConfig;
+use timestep::FixedTimestep;
 
 mod alien;
 mod array_sprite;
+mod attract;
+mod bot;
 mod bottom_area;
 mod canvas;
+mod commander;
+mod controls;
 mod dot_map;
+mod env;
+mod error;
+mod game;
+mod high_score;
+mod input;
+mod palette;
 mod pause;
 mod player;
+mod protocol;
+mod renderer;
+mod replay;
+mod settings;
+mod sound;
 mod sprite;
+mod synth;
+#[cfg(test)]
+mod test_util;
+mod timestep;
 mod top_area;
 mod ufo;
 
-#[derive(PartialEq)]
-enum Scene {
-    Title,
-    Play,
-    Pause,
-    LaunchGame(i32),
-    LaunchStage(i32),
-    ResetStage,
-    Gameover(i32),
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // --headlessか--stdioが指定されていたらウィンドウを開かずに動かす
+    if args
+        .iter()
+        .any(|arg| arg == "--headless" || arg == "--stdio")
+    {
+        if let Err(e) = run_headless(&args) {
+            // 動かせなかった理由を端末に表示する
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    macroquad::Window::from_config(window_conf(), async {
+        if let Err(e) = run().await {
+            // 起動できなかった理由を端末と画面の両方に表示する
+            eprintln!("{}", e);
+            draw_error_screen(&e.to_string()).await;
+        }
+    });
 }
 
-#[macroquad::main(window_conf)]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let mut map = DotMap::new();
-    // キャラクターのドットデータ読み込み
-    let player_data = sprite::ret_dot_data("player");
-    let bullet_player_data = sprite::ret_dot_data("bullet_player");
-    if bullet_player_data.width != 1 {
-        panic!("プレイヤーの弾の幅は1以外は不正です。");
+// --headless <回数>ならボットを遊ばせ、--stdioなら標準入出力で操作する
+// 共通のオプションは[--difficulty <難易度>] [--downsample <縮小率>]
+// --headlessでは[--last-frame]、--stdioでは[--bitmap]も指定できる
+fn run_headless(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let value = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .map(|i| args.get(i + 1).map(String::as_str).unwrap_or_default())
+    };
+    let difficulty = match value("--difficulty") {
+        Some(name) => Difficulty::from_name(name)
+            .ok_or_else(|| format!("{}という難易度はありません。", name))?,
+        None => Difficulty::default(),
+    };
+    let downsample = match value("--downsample") {
+        Some(n) => n
+            .parse()
+            .map_err(|_| "--downsampleには数値を指定してください。")?,
+        None => 1,
+    };
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    if flag("--stdio") {
+        protocol::run(difficulty, downsample, flag("--bitmap"))?;
+        return Ok(());
     }
-    let player_explosion_1_data = sprite::ret_dot_data("player_explosion_1");
-    let player_explosion_2_data = sprite::ret_dot_data("player_explosion_2");
-    let player_bullet_explosion_data = sprite::ret_dot_data("player_bullet_explosion");
-    let ufo_data = sprite::ret_dot_data("ufo");
-    let ufo_explosion_data = sprite::ret_dot_data("ufo_explosion");
-    let shield_data = sprite::ret_dot_data("shield");
-    let octopus_open_data = sprite::ret_dot_data("octopus_open");
-    let octopus_close_data = sprite::ret_dot_data("octopus_close");
-    let crab_banzai_data = sprite::ret_dot_data("crab_banzai");
-    let crab_down_data = sprite::ret_dot_data("crab_down");
-    let squid_open_data = sprite::ret_dot_data("squid_open");
-    let squid_close_data = sprite::ret_dot_data("squid_close");
-    let alien_explosion_data = sprite::ret_dot_data("alien_explosion");
-    let alien_bullet_explosion_data = sprite::ret_dot_data("alien_bullet_explosion");
-    let num_data = sprite::char_dot_data();
-
-    // 各構造体初期化
-    let player_sprite = player_data.create_dot_map();
+    let episodes = value("--headless")
+        .and_then(|n| n.parse().ok())
+        .ok_or("--headlessには回数を指定してください。")?;
+    env::run_bot(episodes, difficulty, downsample, flag("--last-frame"))?;
+    Ok(())
+}
 
-    let num_list: Vec<Vec<u8>> = num_data.iter().map(|n| n.create_dot_map()).collect();
+async fn run() -> Result<(), Box<dyn Error>> {
+    let high_score = HighScore::load(high_score::HIGH_SCORE_PATH).unwrap_or_else(|e| {
+        eprintln!("ハイスコア表を読み込めませんでした: {}", e);
+        HighScore::default()
+    });
+    let mut settings = Settings::load(settings::SETTINGS_PATH).unwrap_or_else(|e| {
+        eprintln!("設定を読み込めませんでした: {}", e);
+        Settings::default()
+    });
+    // 最後に保存した設定
+    let mut saved_settings = settings.clone();
+    // スプライトは起動時に一度だけ読み込み、ゲームを作り直すときはこれを使う
+    let sprites = Sprites::load()?;
+    let mut game = Game::new(&sprites, high_score, settings.difficulty);
+    // 画面上下の表示用のドットデータ
+    let player_sprite = sprites.player.clone();
+    let num_list = sprites.font.clone();
+    // メニューの描画用
+    let font = num_list.clone();
     let mut top = top_area::TopArea::new(num_list.clone());
-
     // 画面下部
-    let mut bottom = bottom_area::BottomArea::new(num_list.clone(), player_sprite.clone());
-    let mut player = Player::new(
-        player_sprite.clone(),
-        player_explosion_1_data.create_dot_map(),
-        player_explosion_2_data.create_dot_map(),
-        load_se_file("audio/player_explosion.wav").await,
-    );
-    let mut player_bullet = Bullet::new(
-        bullet_player_data.create_dot_map(),
-        player_bullet_explosion_data.create_dot_map(),
-        load_se_file("audio/shoot.wav").await,
-    );
+    let mut bottom = bottom_area::BottomArea::new(num_list, player_sprite);
+    // 選べるパレットの一覧
+    let palettes = Palette::all();
+    let palette_names: Vec<String> = palettes.iter().map(|p| p.name.clone()).collect();
 
-    let mut ufo = Ufo::new(
-        ufo_data.create_dot_map(),
-        ufo_explosion_data.create_dot_map(),
-        load_se_file("audio/ufo_flying.wav").await,
-        load_se_file("audio/ufo_explosion.wav").await,
-        num_list.clone(),
-    );
-    let shield = shield_data.create_dot_map();
+    // 効果音を生成する場合の音色
+    let synth = settings.synth.then(|| {
+        SynthConfig::load(synth::SYNTH_PATH).unwrap_or_else(|e| {
+            eprintln!("効果音の音色を読み込めませんでした: {}", e);
+            SynthConfig::default()
+        })
+    });
+    let mut audio = AudioManager::new(Box::new(
+        MacroquadBackend::load(settings.audio_dir.as_deref(), synth.as_ref()).await?,
+    ));
 
-    let mut alian_se = Vec::new();
-    alian_se.push(load_se_file("audio/fastinvader1.wav").await);
-    alian_se.push(load_se_file("audio/fastinvader2.wav").await);
-    alian_se.push(load_se_file("audio/fastinvader3.wav").await);
-    alian_se.push(load_se_file("audio/fastinvader4.wav").await);
-    let mut alien = Alien::new(
-        octopus_open_data.create_dot_map(),
-        octopus_close_data.create_dot_map(),
-        crab_banzai_data.create_dot_map(),
-        crab_down_data.create_dot_map(),
-        squid_open_data.create_dot_map(),
-        squid_close_data.create_dot_map(),
-        alien_explosion_data.create_dot_map(),
-        alian_se,
-        load_se_file("audio/invader_killed.wav").await,
-    );
-    let mut alien_bullets = alien::BulletManage::new(alien_bullet_explosion_data.create_dot_map());
+    // 記録中のリプレイ
+    let mut recording: Option<Replay> = None;
+    // 再生中のリプレイ
+    let mut playback: Option<Playback> = None;
+    // 再生前のハイスコア表(再生中のイニシャル入力で登録された分は再生後に捨てる)
+    let mut saved_high_score: Option<HighScore> = None;
+    // --replay <ファイル>が指定されていたら起動直後にリプレイを再生する
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--replay") {
+        let path = args
+            .get(i + 1)
+            .ok_or("--replayにはファイルを指定してください。")?;
+        let replay = Replay::load(path)?;
+        // 記録したときの難易度で再生する
+        game = Game::new(&sprites, game.high_score.clone(), replay.difficulty);
+        saved_high_score = Some(game.high_score.clone());
+        playback = Some(Playback::new(replay));
+        game.select_players();
+    }
+    // --botが指定されていたらボットに遊ばせ続ける(長時間の動作確認用)
+    let mut bot = args
+        .iter()
+        .any(|arg| arg == "--bot")
+        .then(ReferenceBot::default);
+    // --palette <名前>が指定されていたらそのパレットで表示する
+    if let Some(i) = args.iter().position(|arg| arg == "--palette") {
+        let name = args
+            .get(i + 1)
+            .ok_or("--paletteにはパレット名を指定してください。")?;
+        if !palette_names.contains(name) {
+            return Err(format!("{}というパレットはありません。", name).into());
+        }
+        // 今回の起動中のみ使い、設定ファイルには変更があったときに保存する
+        settings.palette = name.clone();
+        saved_settings.palette = name.clone();
+    }
+    let palette = match palettes.iter().find(|p| p.name == settings.palette) {
+        Some(palette) => palette.clone(),
+        None => {
+            eprintln!("{}というパレットはありません。", settings.palette);
+            settings.palette = palettes[0].name.clone();
+            palettes[0].clone()
+        }
+    };
+    let mut renderer = Renderer::new(palette);
+    // ウィンドウに反映済みの全画面表示と倍率の設定
+    let mut fullscreen = settings.fullscreen;
+    let mut scale = settings.scale;
+    // 表示中の操作設定画面
+    let mut controls_screen: Option<ControlsScreen> = None;
+    // ポーズメニュー
+    let mut pause_menu = PauseMenu::new();
+    // タイトル画面で操作が無いときのデモ
+    let mut attract = Attract::new(&sprites);
+    // メニューを描くゲーム画面と同じ大きさのドットマップ
+    let mut overlay = DotMap::game();
 
-    // 真の場合、画面全体を赤色にする
-    let mut player_exploding = false;
-    // ステージの面数
-    let mut stage = 1;
-    // 起動直後はタイトル画面から始める
-    let mut scene = Scene::Title;
-    // 全体の音量(0〜100)
-    let mut volume = 30;
+    let mut timestep = FixedTimestep::default();
+    // 更新が行われなかったフレームで押された入力(ポーズなど)を次の更新まで持ち越す
+    let mut pending = Input::default();
     loop {
-        // 画面全体を背景色(黒)クリア
-        clear_background(BLACK);
-        let top_texture = top.dot_map2texture(player_exploding);
-        let game_texture = map.dot_map2texture(player_exploding);
-        let bottom_texture = bottom.dot_map2texture(player_exploding);
-        draw_texture_ex(
-            top_texture,
-            0.,
-            0.,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(
-                    (canvas::TOP_WIDTH * canvas::SCALE) as f32,
-                    (canvas::TOP_HEIGHT * canvas::SCALE) as f32,
-                )),
-                ..Default::default()
-            },
-        );
-        draw_texture_ex(
-            game_texture,
-            0.,
-            (canvas::TOP_HEIGHT * canvas::SCALE) as f32,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(
-                    (canvas::GAME_WIDTH * canvas::SCALE) as f32,
-                    (canvas::GAME_HEIGHT * canvas::SCALE) as f32,
-                )),
-                ..Default::default()
-            },
-        );
-        draw_texture_ex(
-            bottom_texture,
-            0.,
-            ((canvas::TOP_HEIGHT + canvas::GAME_HEIGHT) * canvas::SCALE) as f32,
-            WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(
-                    (canvas::BOTTOM_WIDTH * canvas::SCALE) as f32,
-                    (canvas::BOTTOM_HEIGHT * canvas::SCALE) as f32,
-                )),
-                ..Default::default()
-            },
-        );
-        // 得点表示
-        top.draw_score(player_bullet.score);
-        // 残機表示
-        bottom.draw(player.life);
-
-        match scene {
-            Scene::Title => {
-                if is_key_pressed(KeyCode::Enter) {
-                    scene = Scene::LaunchGame(10);
-                    // 前回のドットマップをすべて消す
-                    map.all_clear();
-                    top.all_clear();
-                    bottom.all_clear();
-                }
-                // 画面全体を背景色(黒)クリア
-                clear_background(BLACK);
-                draw_title();
+        // ポーズメニューでの選択
+        let mut pause_action = None;
+        if let Some(screen) = controls_screen.as_mut() {
+            if !screen.update(&mut settings.controls) {
+                controls_screen = None;
             }
-            Scene::Play => {
-                // Escキーが押されていたらポーズ
-                if is_key_pressed(KeyCode::Escape) {
-                    scene = Scene::Pause;
-                }
-                // 更新処理
-                ufo.set_se_volume(volume);
-                ufo.update(&mut map, player_bullet.fire_cnt, alien.live_num);
-
-                alien.set_se_volume(volume);
-                alien.update(&mut map, player_exploding);
-                alien_bullets.update(&mut map, &mut player, &mut alien, player_bullet.score);
-
-                player.set_se_volume(volume);
-                player.update(&mut map);
-                player_bullet.set_se_volume(volume);
-                player_bullet.update(&mut map, &mut player, &mut ufo, &mut alien);
-
-                // エイリアンが全滅したら
-                if alien.live_num <= 0 {
-                    // 次のステージへ進む
-                    scene = Scene::LaunchStage(120);
-                }
-                // プレイヤーの残機が0またはエイリアンがプレイヤーの高さまで侵攻したら
-                if player.life <= 0 || alien.invaded() {
-                    // ゲームオーバー
-                    scene = Scene::Gameover(120);
-                    // 音を止める
-                    ufo.reset();
-                    if alien.invaded() {
-                        // プレイヤーの高さに降りてきた個体を描く
-                        alien.update(&mut map, player_exploding);
-                        // エイリアンに侵攻されていたら爆発を起こす
-                        player.remove(&mut map);
-                    };
-                }
-                // プレイヤーが爆発中は画面全体を赤にする
-                player_exploding = if player.explosion_cnt == None {
-                    false
-                } else {
-                    true
-                };
+        } else if game.scene == Scene::Pause && playback.is_none() {
+            pause_action = pause_menu.update(&mut settings, &palette_names);
+        } else if game.scene == Scene::Title {
+            // 何か押されたらアトラクトモードをタイトルからやり直す
+            if get_last_key_pressed().is_some() {
+                attract.reset();
             }
-            Scene::ResetStage => {
-                // ゲーム開始、ステージ開始時共通
-                scene = Scene::Play;
-                // すべて消す
-                map.all_clear();
+            // ボットに遊ばせているときはすぐに次のゲームを始める
+            if is_key_pressed(KeyCode::Enter) || bot.is_some() {
+                // 前回のゲームの状態を引き継がないよう作り直す
+                game = Game::new(&sprites, game.high_score.clone(), settings.difficulty);
+                game.select_players();
                 top.all_clear();
                 bottom.all_clear();
-                // プレイヤーの下の横線
-                map.draw_holizon_line(canvas::GAME_HEIGHT - 1);
-                // シールド配置
-                for i in 0..4 {
-                    let gap = (shield_data.width as usize + 23) * i;
-                    for dx in 0..shield_data.width as usize {
-                        map.map[20][gap + 33 + dx] = shield[dx];
-                    }
-                    for dx in 0..shield_data.width as usize {
-                        map.map[21][gap + 33 + dx] = shield[shield_data.width as usize + dx];
+                attract.reset();
+                // ボットのゲームは記録せず、リプレイもハイスコア表も保存しない
+                recording = bot.is_none().then(|| Replay::new(settings.difficulty));
+            } else if is_key_pressed(KeyCode::R) {
+                // 最後に遊んだゲームのリプレイを再生する
+                match Replay::load(replay::LAST_REPLAY_PATH) {
+                    Ok(replay) => {
+                        game = Game::new(&sprites, game.high_score.clone(), replay.difficulty);
+                        saved_high_score = Some(game.high_score.clone());
+                        game.select_players();
+                        top.all_clear();
+                        bottom.all_clear();
+                        playback = Some(Playback::new(replay));
                     }
+                    Err(e) => eprintln!("リプレイを読み込めませんでした: {}", e),
                 }
-                alien.reset(stage);
-                alien_bullets.reset();
-                ufo.reset();
+            } else if is_key_pressed(KeyCode::C) {
+                controls_screen = Some(ControlsScreen::new());
+            } else if is_key_pressed(KeyCode::D) {
+                settings.difficulty = settings.difficulty.next();
+                // タイトル画面の残機表示に反映する
+                game = Game::new(&sprites, game.high_score.clone(), settings.difficulty);
             }
-            Scene::LaunchGame(cnt) => {
-                // 一定時間経過したらゲーム開始
-                if cnt < 0 {
-                    scene = Scene::ResetStage;
-
-                    stage = 1;
-                    player.reset_all();
-                    player_bullet.reset_all();
-                } else {
-                    scene = Scene::LaunchGame(cnt - 1);
-                }
+        }
+        // F11キーで全画面表示を切り替える
+        if is_key_pressed(KeyCode::F11) {
+            settings.fullscreen = !settings.fullscreen;
+        }
+        // Tabキーでパレットを切り替える
+        if is_key_pressed(KeyCode::Tab) {
+            let i = palette_names
+                .iter()
+                .position(|name| *name == settings.palette);
+            settings.palette =
+                palette_names[i.map_or(0, |i| (i + 1) % palette_names.len())].clone();
+        }
+        // 表示の設定の変更をウィンドウに反映する
+        if renderer.palette().name != settings.palette {
+            if let Some(palette) = palettes.iter().find(|p| p.name == settings.palette) {
+                renderer.set_palette(palette.clone());
             }
-            Scene::LaunchStage(cnt) => {
-                // 一定時間経過したら次のステージ開始
-                if cnt < 0 {
-                    scene = Scene::ResetStage;
+        }
+        if fullscreen != settings.fullscreen {
+            fullscreen = settings.fullscreen;
+            canvas::set_fullscreen(fullscreen);
+        }
+        if scale != settings.scale {
+            scale = settings.scale;
+            request_new_screen_size(
+                (canvas::SCREEN_WIDTH * scale) as f32,
+                (canvas::SCREEN_HEIGHT * scale) as f32,
+            );
+        }
 
-                    stage += 1;
-                    player.reset_stage();
-                    player_bullet.reset_stage();
-                } else {
-                    scene = Scene::LaunchStage(cnt - 1);
+        let mut keyboard = Input::from_keyboard(&settings.controls);
+        if game.scene == Scene::Pause && playback.is_none() {
+            // ポーズ中はポーズキーもメニューで扱う
+            keyboard.pause = false;
+        }
+        // ポーズの解除とステージのやり直しは入力としてゲームに渡し、リプレイにも記録する
+        let mut exit = false;
+        match pause_action {
+            Some(PauseAction::Resume) => keyboard.pause = true,
+            Some(PauseAction::RestartStage) => keyboard.restart = true,
+            Some(PauseAction::QuitToTitle) => {
+                game = Game::new(&sprites, game.high_score.clone(), settings.difficulty);
+                audio.stop_all();
+            }
+            Some(PauseAction::Exit) => {
+                game = Game::new(&sprites, game.high_score.clone(), settings.difficulty);
+                exit = true;
+            }
+            None => {}
+        }
+        if pause_action.is_some() {
+            // 次にポーズしたときはメニューの先頭から選ぶ
+            pause_menu = PauseMenu::new();
+        }
+        keyboard.pause |= pending.pause;
+        keyboard.restart |= pending.restart;
+        let ticks = timestep.advance(get_frame_time() as f64);
+        pending = Input::default();
+        if ticks == 0 {
+            pending.pause = keyboard.pause;
+            pending.restart = keyboard.restart;
+        }
+        // 描画とは独立に一定間隔で更新処理を行う
+        for _ in 0..ticks {
+            if game.scene == Scene::Title {
+                // 操作設定画面を開いていなければアトラクトモードを進める
+                if controls_screen.is_none() {
+                    attract.update(&game.high_score);
                 }
+                continue;
             }
-            Scene::Gameover(cnt) => {
-                // 一定時間経過したらタイトル画面に戻る
-                if cnt < 0 {
-                    scene = Scene::Title;
-                    player_bullet.score = 0;
-                } else {
-                    scene = Scene::Gameover(cnt - 1);
-                    // プレイヤーを爆発させる
-                    if let Some(cnt) = player.explosion_cnt {
-                        if cnt <= player.const_max_explosion_cnt {
-                            player.update(&mut map);
-                        }
+            // リプレイ再生中は記録された入力でゲームを進める
+            let input = match (playback.as_mut(), bot.as_mut()) {
+                (Some(p), _) => p.next_input(),
+                // ボットに遊ばせているときもポーズとやり直しはキーボードで操作する
+                (None, Some(bot)) => Some(Input {
+                    pause: keyboard.pause,
+                    restart: keyboard.restart,
+                    ..bot.input(&GameView::new(&game))
+                }),
+                (None, None) => Some(keyboard),
+            };
+            // 押された瞬間の入力は最初の更新でのみ有効にする
+            keyboard.pause = false;
+            keyboard.restart = false;
+            match input {
+                Some(input) => {
+                    if let Some(replay) = recording.as_mut() {
+                        replay.push(input);
                     }
+                    // 更新処理
+                    game.update(&input);
                 }
-                draw_gameover_message();
+                None => {
+                    // リプレイの再生が終わったらタイトル画面に戻る
+                    game = Game::new(&sprites, game.high_score.clone(), settings.difficulty);
+                }
+            }
+        }
+        if game.scene == Scene::Title {
+            playback = None;
+            if let Some(high_score) = saved_high_score.take() {
+                game.high_score = high_score;
             }
-            Scene::Pause => {
-                // Escキーが押されていたらポーズ解除
-                if is_key_pressed(KeyCode::Escape) {
-                    scene = Scene::Play;
+            // ゲームが終わったらリプレイとハイスコア表を保存する
+            if let Some(replay) = recording.take() {
+                if let Err(e) = replay.save(replay::LAST_REPLAY_PATH) {
+                    eprintln!("リプレイを保存できませんでした: {}", e);
                 }
-                volume = draw_pause(volume);
+                if let Err(e) = game.high_score.save(high_score::HIGH_SCORE_PATH) {
+                    eprintln!("ハイスコア表を保存できませんでした: {}", e);
+                }
+            }
+        }
+        // ポーズ画面や操作設定画面を閉じた時点で変更された設定を保存する
+        if settings != saved_settings
+            && (exit || game.scene != Scene::Pause && controls_screen.is_none())
+        {
+            if let Err(e) = settings.save(settings::SETTINGS_PATH) {
+                eprintln!("設定を保存できませんでした: {}", e);
+            }
+            saved_settings = settings.clone();
+        }
+        if exit {
+            return Ok(());
+        }
+        audio.apply_settings(&settings);
+        for request in game.take_sound_requests() {
+            audio.request(request);
+        }
+
+        // 画面全体を背景色(黒)クリア
+        clear_background(BLACK);
+        // デモプレイ中はデモのゲームを表示する
+        let shown = attract.demo().unwrap_or(&game);
+        // 得点表示(ハイスコアはプレイ中の得点が上回ったらその得点を表示)
+        let scores = shown.scores();
+        let hi_score = scores
+            .into_iter()
+            .fold(game.high_score.top_score(), i32::max);
+        top.draw_score(scores, hi_score);
+        // 残機表示
+        bottom.draw(shown.player.life);
+        if let Some(partner) = &shown.partner {
+            bottom.draw_partner(partner.player.life);
+        }
+        if let Some(commander) = &shown.commander {
+            bottom.draw_commander(
+                commander.column_x(&shown.alien),
+                shown.ufo.can_launch(shown.alien.live_num),
+            );
+        }
+        // メニュー表示中はゲーム画面の代わりにメニューを描く
+        let show_menu = if let Some(screen) = &controls_screen {
+            screen.draw(&settings.controls, &mut overlay, &font);
+            true
+        } else if game.scene == Scene::Pause && playback.is_none() {
+            pause_menu.draw(&settings, &mut overlay, &font);
+            true
+        } else {
+            false
+        };
+        // 協力プレイの2人目のキャノンは2人目の色で描く
+        let partner_areas: Vec<ColorArea> = shown
+            .partner_area()
+            .map(|(char_y, x)| ColorArea {
+                char_y,
+                x,
+                color: renderer.palette().partner,
+            })
+            .into_iter()
+            .collect();
+        let (game_plane, game_areas) = if show_menu {
+            (&overlay, &[][..])
+        } else if game.scene == Scene::Title && attract.demo().is_none() {
+            // 得点表、ハイスコア表
+            (&attract.plane, &[][..])
+        } else {
+            (&shown.map, &partner_areas[..])
+        };
+        // 変化のあった領域のみテクスチャを書き換えて描画
+        renderer.draw(
+            &[
+                (top.plane(), 0, &[]),
+                (game_plane, canvas::TOP_HEIGHT, game_areas),
+                (
+                    bottom.plane(),
+                    canvas::TOP_HEIGHT + canvas::GAME_HEIGHT,
+                    &[],
+                ),
+            ],
+            shown.player_exploding,
+        );
+
+        match game.scene {
+            // 操作設定画面を開いていなければタイトルを表示
+            Scene::Title if controls_screen.is_none() && attract.page == Page::Title => {
+                // 画面全体を背景色(黒)クリア
+                clear_background(BLACK);
+                draw_title(settings.difficulty);
             }
+            // 交互プレイではどちらのプレイヤーのゲームオーバーかも表示する
+            Scene::Gameover(_) => {
+                draw_gameover_message((game.players == 2 && !game.coop).then_some(game.current))
+            }
+            _ => {}
+        }
+        if playback.is_some() {
+            draw_replay_message();
         }
         next_frame().await
     }
 }
 
-fn draw_title() {
+fn draw_title(difficulty: Difficulty) {
     let text = "Invader";
-    let font_size = 120.;
+    let font_size = canvas::dot2pix(40);
     let str_size = measure_text(text, None, font_size as _, 1.0);
     // 指定座標は文字の左下
     draw_text(
         text,
         screen_width() / 2. - str_size.width / 2.,
-        180.,
+        canvas::dot2y(60),
         font_size,
         RED,
     );
     let text = "Press Enter";
-    let font_size = 60.;
+    let font_size = canvas::dot2pix(20);
+    let str_size = measure_text(text, None, font_size as _, 1.0);
+    // 指定座標は文字の左下
+    draw_text(
+        text,
+        screen_width() / 2. - str_size.width / 2.,
+        canvas::dot2y(90),
+        font_size,
+        RED,
+    );
+    let text = "Press R to replay last game";
+    let font_size = canvas::dot2pix(13);
+    let str_size = measure_text(text, None, font_size as _, 1.0);
+    // 指定座標は文字の左下
+    draw_text(
+        text,
+        screen_width() / 2. - str_size.width / 2.,
+        canvas::dot2y(106),
+        font_size,
+        RED,
+    );
+    let text = "Press C to change controls";
+    let font_size = canvas::dot2pix(13);
     let str_size = measure_text(text, None, font_size as _, 1.0);
     // 指定座標は文字の左下
     draw_text(
         text,
         screen_width() / 2. - str_size.width / 2.,
-        270.,
+        canvas::dot2y(119),
+        font_size,
+        RED,
+    );
+    let text = format!("Press D to change difficulty: {}", difficulty.name());
+    let font_size = canvas::dot2pix(13);
+    let str_size = measure_text(&text, None, font_size as _, 1.0);
+    // 指定座標は文字の左下
+    draw_text(
+        &text,
+        screen_width() / 2. - str_size.width / 2.,
+        canvas::dot2y(132),
+        font_size,
+        RED,
+    );
+}
+
+// リプレイ再生中表示
+fn draw_replay_message() {
+    let text = "Replay";
+    let font_size = canvas::dot2pix(13);
+    let str_size = measure_text(text, None, font_size as _, 1.0);
+    // 指定座標は文字の左下
+    draw_text(
+        text,
+        canvas::dot2x(canvas::SCREEN_WIDTH - 3) - str_size.width,
+        canvas::dot2y(canvas::SCREEN_HEIGHT - 3),
         font_size,
         RED,
     );
 }
 
 // ゲームオーバー表示
-fn draw_gameover_message() {
+fn draw_gameover_message(player: Option<usize>) {
     let text = "Game over";
-    let font_size = 120.;
+    let font_size = canvas::dot2pix(40);
     let str_size = measure_text(text, None, font_size as _, 1.0);
     // 指定座標は文字の左下
     draw_text(
         text,
         screen_width() / 2. - str_size.width / 2.,
-        190.,
+        canvas::dot2y(63),
         font_size,
         RED,
     );
+    if let Some(player) = player {
+        let text = format!("Player {}", player + 1);
+        let font_size = canvas::dot2pix(20);
+        let str_size = measure_text(&text, None, font_size as _, 1.0);
+        draw_text(
+            &text,
+            screen_width() / 2. - str_size.width / 2.,
+            canvas::dot2y(83),
+            font_size,
+            RED,
+        );
+    }
 }
 
-async fn load_se_file(path: &str) -> Sound {
-    load_sound(path).await.unwrap()
+// エラーメッセージを表示し、Escキーが押されるまで待つ
+async fn draw_error_screen(message: &str) {
+    let font_size = 30.;
+    loop {
+        if is_key_pressed(KeyCode::Escape) {
+            break;
+        }
+        clear_background(BLACK);
+        draw_text("Error", 20., 60., 60., RED);
+        // 長いメッセージは画面幅で折り返す
+        let mut y = 120.;
+        let mut line = String::new();
+        for c in message.chars() {
+            line.push(c);
+            if screen_width() - 40. < measure_text(&line, None, font_size as _, 1.0).width {
+                let last = line.pop().unwrap_or_default();
+                draw_text(&line, 20., y, font_size, WHITE);
+                y += font_size;
+                line = last.to_string();
+            }
+        }
+        draw_text(&line, 20., y, font_size, WHITE);
+        draw_text(
+            "Press Escape to exit",
+            20.,
+            screen_height() - 20.,
+            font_size,
+            RED,
+        );
+        next_frame().await
+    }
 }
 
 // ウィンドウサイズを指定
 fn window_conf() -> Conf {
+    // 設定ファイルの倍率と全画面表示で開く
+    let settings = Settings::load(settings::SETTINGS_PATH).unwrap_or_default();
+    let scale = settings.scale;
     Conf {
         window_title: "invader-macroquad".to_owned(),
-        window_width: canvas::GAME_WIDTH * canvas::SCALE,
-        window_height: (canvas::TOP_HEIGHT + canvas::GAME_HEIGHT + canvas::BOTTOM_HEIGHT)
-            * canvas::SCALE,
+        window_width: canvas::SCREEN_WIDTH * scale,
+        window_height: canvas::SCREEN_HEIGHT * scale,
+        fullscreen: settings.fullscreen,
+        // 大きさを変えても整数倍に拡大して中央に表示する
+        window_resizable: true,
         ..Default::default()
     }
 }
@@ -0,0 +1,243 @@
+use crate::canvas;
+use crate::error::invalid_data;
+use std::fs;
+use std::io;
+
+// ユーザー定義のパレットを置くディレクトリ
+pub const PALETTE_DIR: &str = "palettes";
+
+pub enum Color {
+    Red,       // 赤色
+    Purple,    // 紫色
+    Blue,      // 青色
+    Green,     // 緑色
+    Turquoise, // 水色
+    Yellow,    // 黄色
+    White,     // 白色
+}
+// 指定した色に対応するrgbaの値を返す
+pub fn set_color(color: Color) -> [u8; 4] {
+    match color {
+        Color::Red => [210, 0, 0, 255],          // 赤色
+        Color::Purple => [220, 20, 230, 255],    // 紫色
+        Color::Blue => [83, 83, 241, 255],       // 青色
+        Color::Green => [98, 222, 109, 255],     // 緑色
+        Color::Turquoise => [68, 200, 210, 255], // 水色
+        Color::Yellow => [220, 210, 30, 255],    // 黄色
+        Color::White => [220, 220, 220, 255],    // 白色
+    }
+}
+
+// 画面の各領域のドットの色
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Palette {
+    pub name: String,
+    pub background: [u8; 4],     // 背景(ドットが0の部分)
+    pub exploding: [u8; 4],      // プレイヤーが爆発中の全ドット
+    pub top: [u8; 4],            // 画面上部
+    pub bottom: [u8; 4],         // 画面下部
+    pub partner: [u8; 4],        // 協力プレイの2人目のキャノン
+    pub game_rows: Vec<[u8; 4]>, // ゲーム画面の文字単位の行ごと
+}
+
+impl Palette {
+    // すべて同じ色で塗る
+    fn mono(name: &str, color: [u8; 4], exploding: [u8; 4], partner: [u8; 4]) -> Self {
+        Palette {
+            name: name.to_owned(),
+            background: [0, 0, 0, 255],
+            exploding,
+            top: color,
+            bottom: color,
+            partner,
+            game_rows: vec![color; (canvas::GAME_HEIGHT / 8) as usize],
+        }
+    }
+    // 白黒のモニタそのままの表示
+    pub fn original() -> Self {
+        Self::mono(
+            "original",
+            set_color(Color::White),
+            set_color(Color::Red),
+            set_color(Color::Yellow),
+        )
+    }
+    // モニタに色付きのセロハンを貼った表示
+    pub fn overlay() -> Self {
+        let game_rows = (0..(canvas::GAME_HEIGHT / 8) as usize)
+            .map(|char_y| {
+                let color = match char_y {
+                    0 | 20..=22 | 25 => Color::Red,
+                    1 | 12..=15 => Color::Purple,
+                    2 | 3 => Color::Blue,
+                    4..=7 => Color::Green,
+                    8..=11 | 23 | 24 => Color::Turquoise,
+                    _ => Color::Yellow,
+                };
+                set_color(color)
+            })
+            .collect();
+        Palette {
+            name: "overlay".to_owned(),
+            background: [0, 0, 0, 255],
+            exploding: set_color(Color::Red),
+            top: set_color(Color::White),
+            bottom: set_color(Color::Turquoise),
+            partner: set_color(Color::Yellow),
+            game_rows,
+        }
+    }
+    // 緑色の単色モニタ風の表示
+    pub fn green() -> Self {
+        Self::mono(
+            "green",
+            [51, 255, 102, 255],
+            [20, 120, 40, 255],
+            [200, 255, 210, 255],
+        )
+    }
+    // 組み込みのパレットとPALETTE_DIRにあるパレットの一覧
+    pub fn all() -> Vec<Palette> {
+        let mut palettes = vec![Self::original(), Self::overlay(), Self::green()];
+        let mut paths: Vec<_> = match fs::read_dir(PALETTE_DIR) {
+            Ok(dir) => dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        for path in paths {
+            let path = path.to_string_lossy();
+            match Self::load(&path) {
+                Ok(palette) => palettes.push(palette),
+                Err(e) => eprintln!("パレット{}を読み込めませんでした: {}", path, e),
+            }
+        }
+        palettes
+    }
+    // 1行に「項目名 R G B」で色を指定する
+    // 指定しなかった項目はoriginalと同じ色になる
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let name = std::path::Path::new(path)
+            .file_stem()
+            .map_or(path.into(), |stem| stem.to_string_lossy());
+        let mut palette = Self::original();
+        palette.name = name.into_owned();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            palette
+                .parse_line(line)
+                .ok_or_else(|| invalid_data(format!("{}行目が不正です。", i + 1)))?;
+        }
+        Ok(palette)
+    }
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (key, rgb) = match words.as_slice() {
+            [key, r, g, b] => (*key, [*r, *g, *b]),
+            _ => return None,
+        };
+        let mut color = [0, 0, 0, 255];
+        for (c, v) in color.iter_mut().zip(rgb) {
+            *c = v.parse().ok()?;
+        }
+        match key {
+            "background" => self.background = color,
+            "exploding" => self.exploding = color,
+            "top" => self.top = color,
+            "bottom" => self.bottom = color,
+            "partner" => self.partner = color,
+            "game" => self.game_rows.fill(color),
+            // row<n>またはrow<n>-<m>で文字単位の行を指定する
+            _ => {
+                let rows = key.strip_prefix("row")?;
+                let (from, to) = rows.split_once('-').unwrap_or((rows, rows));
+                let (from, to): (usize, usize) = (from.parse().ok()?, to.parse().ok()?);
+                if to < from {
+                    return None;
+                }
+                self.game_rows.get_mut(from..=to)?.fill(color);
+            }
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn row_keys_color_the_given_rows() {
+        let original = Palette::original();
+        let mut palette = Palette::original();
+        palette.parse_line("row3 1 2 3").unwrap();
+        palette.parse_line("row5-7 4 5 6").unwrap();
+        for (i, row) in palette.game_rows.iter().enumerate() {
+            let expected = match i {
+                3 => [1, 2, 3, 255],
+                5..=7 => [4, 5, 6, 255],
+                _ => original.game_rows[i],
+            };
+            assert_eq!(*row, expected, "row{}", i);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_rows_and_values() {
+        let last = canvas::GAME_HEIGHT / 8 - 1;
+        let lines = [
+            format!("row{} 1 2 3", last + 1),
+            format!("row{}-{} 1 2 3", last - 1, last + 1),
+            "row5-2 1 2 3".to_owned(),
+            "row3-2 1 2 3".to_owned(),
+            "row 1 2 3".to_owned(),
+            "rowx 1 2 3".to_owned(),
+            "sky 1 2 3".to_owned(),
+            "top 1 2 256".to_owned(),
+            "top 1 2".to_owned(),
+            "top 1 2 3 4".to_owned(),
+        ];
+        for line in &lines {
+            let mut palette = Palette::original();
+            assert_eq!(palette.parse_line(line), None, "{}", line);
+        }
+        let mut palette = Palette::original();
+        assert!(palette
+            .parse_line(&format!("row{}-{} 1 2 3", last, last))
+            .is_some());
+    }
+
+    #[test]
+    fn load_keeps_original_colors_for_unset_items() {
+        let path = temp_path("palette-test.txt");
+        fs::write(&path, "// コメント\n\ntop 1 2 3\n  row0 4 5 6\n").unwrap();
+        let palette = Palette::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let mut expected = Palette::original();
+        expected.name = std::path::Path::new(&path)
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        expected.top = [1, 2, 3, 255];
+        expected.game_rows[0] = [4, 5, 6, 255];
+        assert_eq!(palette, expected);
+    }
+
+    #[test]
+    fn load_reports_the_bad_line() {
+        let path = temp_path("palette-bad.txt");
+        fs::write(&path, "top 1 2 3\nrow99 1 2 3\n").unwrap();
+        let err = Palette::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("2行目"), "{}", err);
+    }
+}
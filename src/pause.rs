@@ -1,103 +1,221 @@
+use crate::array_sprite::array_string;
+use crate::controls::{ControlsScreen, MenuInput};
+use crate::dot_map::DotMap;
+use crate::settings::{Settings, MAX_SCALE};
 use macroquad::prelude::*;
 
-use crate::canvas::dot2pix;
+// ポーズメニューの項目
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Item {
+    Resume,
+    Volume,
+    SeVolume,
+    Mute,
+    Controls,
+    Display,
+    RestartStage,
+    QuitToTitle,
+    Exit,
+}
 
-pub fn draw_pause(volume: i32) -> i32 {
-    draw_pause_message();
-    draw_change_volume(volume)
+impl Item {
+    const ALL: [Item; 9] = [
+        Item::Resume,
+        Item::Volume,
+        Item::SeVolume,
+        Item::Mute,
+        Item::Controls,
+        Item::Display,
+        Item::RestartStage,
+        Item::QuitToTitle,
+        Item::Exit,
+    ];
+    fn label(self) -> &'static str {
+        match self {
+            Item::Resume => "RESUME",
+            Item::Volume => "VOLUME",
+            Item::SeVolume => "SE VOLUME",
+            Item::Mute => "MUTE",
+            Item::Controls => "CONTROLS",
+            Item::Display => "DISPLAY",
+            Item::RestartStage => "RESTART STAGE",
+            Item::QuitToTitle => "QUIT TO TITLE",
+            Item::Exit => "EXIT",
+        }
+    }
 }
 
-fn draw_pause_message() {
-    crate::canvas::draw_screen(Color::new(0.1, 0.1, 0.1, 0.8));
-    let text = "Pause";
-    let font_size = dot2pix(40);
-    let str_size = measure_text(text, None, font_size as _, 1.0);
-    // 指定座標は文字の左下
-    draw_text(
-        text,
-        screen_width() / 2. - str_size.width / 2.,
-        dot2pix(40),
-        font_size,
-        RED,
-    );
-    let text = "Press Escape key";
-    let font_size = dot2pix(20);
-    let str_size = measure_text(text, None, font_size as _, 1.0);
-    // 指定座標は文字の左下
-    draw_text(
-        text,
-        screen_width() / 2. - str_size.width / 2.,
-        dot2pix(70),
-        font_size,
-        RED,
-    );
-    let text = "to resume game";
-    let font_size = dot2pix(20);
-    let str_size = measure_text(text, None, font_size as _, 1.0);
-    // 指定座標は文字の左下
-    draw_text(
-        text,
-        screen_width() / 2. - str_size.width / 2.,
-        dot2pix(90),
-        font_size,
-        RED,
-    );
+// 表示設定の項目
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DisplayItem {
+    Palette,
+    Scale,
+    Fullscreen,
+    Back,
 }
 
-fn draw_change_volume(mut volume: i32) -> i32 {
-    let text = "Change volume(0~100)";
-    let font_size = dot2pix(20);
-    let str_size = measure_text(text, None, font_size as _, 1.0);
-    // 指定座標は文字の左下
-    draw_text(
-        text,
-        screen_width() / 2. - str_size.width / 2.,
-        dot2pix(120),
-        font_size,
-        WHITE,
-    );
-    let mut up_color_thick = (WHITE, 1.);
-    let mut down_color_thick = (WHITE, 1.);
-    if is_key_down(KeyCode::Up) {
-        volume += 1;
-        up_color_thick = (YELLOW, 4.);
+impl DisplayItem {
+    const ALL: [DisplayItem; 4] = [
+        DisplayItem::Palette,
+        DisplayItem::Scale,
+        DisplayItem::Fullscreen,
+        DisplayItem::Back,
+    ];
+    fn label(self) -> &'static str {
+        match self {
+            DisplayItem::Palette => "PALETTE",
+            DisplayItem::Scale => "SCALE",
+            DisplayItem::Fullscreen => "FULLSCREEN",
+            DisplayItem::Back => "BACK",
+        }
     }
-    if is_key_down(KeyCode::Down) {
-        volume -= 1;
-        down_color_thick = (YELLOW, 4.);
+}
+
+// 表示中のページ
+enum Page {
+    Main,
+    Controls(ControlsScreen),
+    Display(usize), // 選択中の表示設定の項目
+}
+
+// ゲームの進行に関わるメニューの選択結果
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PauseAction {
+    Resume,
+    RestartStage,
+    QuitToTitle,
+    Exit,
+}
+
+pub struct PauseMenu {
+    cursor: usize, // 選択中の項目
+    page: Page,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        PauseMenu {
+            cursor: 0,
+            page: Page::Main,
+        }
     }
-    if volume < 0 {
-        volume = 0;
+    // 設定の変更はsettingsに反映し、ゲームの進行に関わる選択は戻り値で返す
+    // palettesは選べるパレット名の一覧
+    pub fn update(&mut self, settings: &mut Settings, palettes: &[String]) -> Option<PauseAction> {
+        match &mut self.page {
+            Page::Main => {}
+            Page::Controls(screen) => {
+                if !screen.update(&mut settings.controls) {
+                    self.page = Page::Main;
+                }
+                return None;
+            }
+            Page::Display(cursor) => {
+                let menu = MenuInput::from_keyboard(&settings.controls);
+                *cursor = menu.move_cursor(*cursor, DisplayItem::ALL.len());
+                let dir = menu.right as i32 - menu.left as i32;
+                match DisplayItem::ALL[*cursor] {
+                    DisplayItem::Palette if dir != 0 => {
+                        let i = palettes.iter().position(|p| *p == settings.palette);
+                        let i = i.map_or(0, |i| {
+                            (i as i32 + dir).rem_euclid(palettes.len() as i32) as usize
+                        });
+                        settings.palette = palettes[i].clone();
+                    }
+                    DisplayItem::Scale => {
+                        settings.scale = (settings.scale + dir).clamp(1, MAX_SCALE);
+                    }
+                    DisplayItem::Fullscreen if dir != 0 || menu.select => {
+                        settings.fullscreen = !settings.fullscreen;
+                    }
+                    DisplayItem::Back if menu.select => self.page = Page::Main,
+                    _ => {}
+                }
+                if menu.back {
+                    self.page = Page::Main;
+                }
+                return None;
+            }
+        }
+        let menu = MenuInput::from_keyboard(&settings.controls);
+        if menu.back {
+            // ポーズキーでもメニューを閉じて再開する
+            return Some(PauseAction::Resume);
+        }
+        self.cursor = menu.move_cursor(self.cursor, Item::ALL.len());
+        let item = Item::ALL[self.cursor];
+        let dir = menu.right as i32 - menu.left as i32;
+        match item {
+            Item::Volume => settings.volume = (settings.volume + 5 * dir).clamp(0, 100),
+            Item::SeVolume => settings.se_volume = (settings.se_volume + 5 * dir).clamp(0, 100),
+            Item::Mute if dir != 0 || menu.select => settings.mute = !settings.mute,
+            _ => {}
+        }
+        if !menu.select {
+            return None;
+        }
+        match item {
+            Item::Resume => Some(PauseAction::Resume),
+            Item::Volume | Item::SeVolume | Item::Mute => None,
+            Item::Controls => {
+                self.page = Page::Controls(ControlsScreen::new());
+                None
+            }
+            Item::Display => {
+                self.page = Page::Display(0);
+                None
+            }
+            Item::RestartStage => Some(PauseAction::RestartStage),
+            Item::QuitToTitle => Some(PauseAction::QuitToTitle),
+            Item::Exit => Some(PauseAction::Exit),
+        }
     }
-    if 100 < volume {
-        volume = 100;
+    // ゲーム画面と同じ大きさのドットマップにメニューを描く
+    pub fn draw(&self, settings: &Settings, plane: &mut DotMap, font: &[Vec<u8>]) {
+        match &self.page {
+            Page::Main => {
+                plane.all_clear();
+                array_string(&mut plane.map, IVec2::new(92, 24), "PAUSE", font);
+                for (i, item) in Item::ALL.iter().enumerate() {
+                    let y = 56 + 16 * i as i32;
+                    draw_cursor(plane, font, y, i == self.cursor);
+                    array_string(&mut plane.map, IVec2::new(48, y), item.label(), font);
+                    let value = match item {
+                        Item::Volume => format!("{:>3}", settings.volume),
+                        Item::SeVolume => format!("{:>3}", settings.se_volume),
+                        Item::Mute if settings.mute => " ON".to_owned(),
+                        Item::Mute => "OFF".to_owned(),
+                        _ => continue,
+                    };
+                    array_string(&mut plane.map, IVec2::new(144, y), &value, font);
+                }
+            }
+            Page::Controls(screen) => screen.draw(&settings.controls, plane, font),
+            Page::Display(cursor) => {
+                plane.all_clear();
+                array_string(&mut plane.map, IVec2::new(84, 24), "DISPLAY", font);
+                for (i, item) in DisplayItem::ALL.iter().enumerate() {
+                    let y = 56 + 16 * i as i32;
+                    draw_cursor(plane, font, y, i == *cursor);
+                    array_string(&mut plane.map, IVec2::new(48, y), item.label(), font);
+                    let value = match item {
+                        DisplayItem::Palette => settings.palette.to_uppercase(),
+                        DisplayItem::Scale => settings.scale.to_string(),
+                        DisplayItem::Fullscreen if settings.fullscreen => "ON".to_owned(),
+                        DisplayItem::Fullscreen => "OFF".to_owned(),
+                        DisplayItem::Back => String::new(),
+                    };
+                    // 画面の右端ではみ出す分は表示しない
+                    let value: String = value.chars().take(7).collect();
+                    array_string(&mut plane.map, IVec2::new(144, y), &value, font);
+                }
+            }
+        }
     }
-    let top = Vec2::new(screen_width() / 2., dot2pix(140));
-    let dx = dot2pix(5);
-    let dy = dot2pix(14);
-    draw_triangle_lines(
-        top,
-        Vec2::new(top.x - dx, top.y + dy),
-        Vec2::new(top.x + dx, top.y + dy),
-        up_color_thick.1,
-        up_color_thick.0,
-    );
-    let text = &volume.to_string();
-    let str_size = measure_text(text, None, font_size as _, 1.0);
-    draw_text(
-        text,
-        screen_width() / 2. - str_size.width / 2.,
-        dot2pix(175),
-        font_size,
-        WHITE,
-    );
-    let bottom = Vec2::new(screen_width() / 2., dot2pix(200));
-    draw_triangle_lines(
-        bottom,
-        Vec2::new(bottom.x - dx, bottom.y - dy),
-        Vec2::new(bottom.x + dx, bottom.y - dy),
-        down_color_thick.1,
-        down_color_thick.0,
-    );
-    volume
+}
+
+// 選択中の項目の左に印を付ける
+fn draw_cursor(plane: &mut DotMap, font: &[Vec<u8>], y: i32, selected: bool) {
+    let mark = if selected { ">" } else { " " };
+    array_string(&mut plane.map, IVec2::new(32, y), mark, font);
 }
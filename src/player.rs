@@ -1,12 +1,14 @@
 use crate::alien::Alien;
 use crate::canvas;
+use crate::input::Input;
+use crate::sound::{Se, SoundQueue};
 use crate::ufo::Ufo;
 use crate::{array_sprite::ArraySprite, dot_map::DotMap};
-use macroquad::audio::*;
 use macroquad::prelude::*;
 // プレイヤーの弾のスピード
 const PLAYER_BULLET_DELTA: i32 = 4;
 
+#[derive(Clone)]
 pub struct Bullet {
     pos: IVec2,                  // 左上位置
     live: bool,                  // 弾が存在しているか否か
@@ -16,12 +18,10 @@ pub struct Bullet {
     pub score: i32,              // 獲得点数
     sprite: Vec<u8>,             // 左側から縦8ピクセルずつを8bitのベクタで表す
     explosion_sprite: Vec<u8>,   // 爆発画像
-    se: Sound,
-    se_volume: f32, // 発射音の音量(0〜1)
 }
 
 impl Bullet {
-    pub fn new(sprite: Vec<u8>, explosion_sprite: Vec<u8>, se: Sound) -> Self {
+    pub fn new(sprite: Vec<u8>, explosion_sprite: Vec<u8>) -> Self {
         Bullet {
             pos: IVec2::new(0, 0),
             live: false,
@@ -31,15 +31,10 @@ impl Bullet {
             sprite,
             explosion_sprite,
             score: 0,
-            se,
-            se_volume: 0.3,
         }
     }
-    pub fn set_se_volume(&mut self, volume: i32) {
-        self.se_volume = (volume as f32) / 100.;
-    }
     // 弾を発射
-    fn fire(&mut self, x: i32, y: i32) {
+    fn fire(&mut self, x: i32, y: i32, se: &mut SoundQueue) {
         if self.live {
             return;
         }
@@ -48,26 +43,30 @@ impl Bullet {
         self.live = true;
         self.fire_cnt += 1;
         self.explosion_effect_show = false;
-        // wasmでは再生しない
-        #[cfg(not(target_arch = "wasm32"))]
-        play_sound(
-            self.se,
-            PlaySoundParams {
-                looped: false,
-                volume: self.se_volume,
-            },
-        );
+        se.play(Se::Shoot);
+    }
+    // 弾が画面上にあれば真
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+    // 画面上にある場合は弾の左上位置
+    pub fn live_pos(&self) -> Option<IVec2> {
+        self.live.then_some(self.pos)
     }
     pub fn reset_all(&mut self) {
         self.reset_stage();
         self.score = 0;
     }
     pub fn reset_stage(&mut self) {
+        self.resume();
+        self.fire_cnt = 0;
+    }
+    // 弾を消して射撃禁止から再開する(交互プレイの交代後は累計射撃数を引き継ぐ)
+    pub fn resume(&mut self) {
         self.live = false;
         self.explosion_effect_show = false;
         // 開始後一定時間は射撃禁止
         self.ban_fire_cnt = Some(80);
-        self.fire_cnt = 0;
     }
     pub fn update(
         &mut self,
@@ -75,6 +74,8 @@ impl Bullet {
         player: &mut Player,
         ufo: &mut Ufo,
         alien: &mut Alien,
+        input: &Input,
+        se: &mut SoundQueue,
     ) {
         // 弾が存在していたら
         if self.live {
@@ -90,30 +91,25 @@ impl Bullet {
                 self.ban_fire_cnt = Some(15);
                 self.explosion_effect_show = true;
                 // 自身のx座標が爆発エフェクトの中心になるようずらす
-                self.pos.x = self.pos.x - self.explosion_sprite.len() as i32 / 2;
+                self.pos.x -= self.explosion_sprite.len() as i32 / 2;
             } else {
                 // 移動後の弾の部分に何か物体が存在したら
                 if self.is_collide(dot_map) {
-                    self.collided(dot_map, ufo, alien);
+                    self.collided(dot_map, ufo, alien, se);
                 }
             }
         } else {
-            // 弾が画面上に無く、射撃可能状態で、プレイヤーが生きていて、かつ発射ボタンが押された場合(スペース、Enter)
-            if self.ban_fire_cnt == None
-                && player.explosion_cnt == None
-                && (is_key_down(KeyCode::Z)
-                    || is_key_down(KeyCode::Space)
-                    || is_key_down(KeyCode::Enter))
-            {
-                self.fire(player.pos.x + 7, player.pos.y - 8);
+            // 弾が画面上に無く、射撃可能状態で、プレイヤーが生きていて、かつ発射ボタンが押された場合
+            if self.ban_fire_cnt.is_none() && player.explosion_cnt.is_none() && input.fire {
+                self.fire(player.pos.x + 7, player.pos.y - 8, se);
                 // プレイヤーの一つ上の行の判定
                 if self.is_collide(dot_map) {
-                    self.collided(dot_map, ufo, alien);
+                    self.collided(dot_map, ufo, alien, se);
                 }
             }
         }
         // スコアボーナス
-        if 1500 <= self.score && !player.life_up {
+        if player.bonus_score.is_some_and(|bonus| bonus <= self.score) && !player.life_up {
             player.life += 1;
             // 残機が増えるのは1度だけ
             player.life_up = true;
@@ -121,7 +117,13 @@ impl Bullet {
         self.draw(dot_map);
     }
 
-    fn collided(&mut self, dot_map: &mut DotMap, ufo: &mut Ufo, alien: &mut Alien) {
+    fn collided(
+        &mut self,
+        dot_map: &mut DotMap,
+        ufo: &mut Ufo,
+        alien: &mut Alien,
+        se: &mut SoundQueue,
+    ) {
         // 何かに衝突したので弾を消す
         self.live = false;
         self.ban_fire_cnt = Some(15);
@@ -130,9 +132,9 @@ impl Bullet {
         // 衝突したのがUFOだった場合
         if self.pos.y / 8 < 2 {
             // UFOの爆発エフェクト表示中でなければ
-            if ufo.explosion.show_cnt == None {
+            if ufo.explosion.show_cnt.is_none() {
                 // UFOの撃破時には点数を加算
-                self.score += ufo.hit_player_bullet(dot_map, self.fire_cnt);
+                self.score += ufo.hit_player_bullet(dot_map, self.fire_cnt, se);
             }
             // 爆発エフェクトは表示しない
             self.explosion_effect_show = false;
@@ -142,13 +144,13 @@ impl Bullet {
             if let Some(i) = alien.pos2index(self.pos) {
                 // 撃破したエイリアンの点数を追加
                 self.score += Alien::index2score(i);
-                alien.remove(dot_map, i);
+                alien.remove(dot_map, i, se);
                 // 爆発エフェクトは表示しない
                 self.explosion_effect_show = false;
             }
         }
         // 自身のx座標が爆発エフェクトの中心になるようずらす
-        self.pos.x = self.pos.x - 4;
+        self.pos.x -= 4;
         self.pos.y += 2;
     }
 
@@ -191,54 +193,52 @@ impl ArraySprite for Bullet {
     }
 }
 
+#[derive(Clone)]
 pub struct Player {
     width: i32,                       // 描画サイズの幅
     pub pos: IVec2,                   // 左上位置
     pre_pos: IVec2,                   // 前回描画時の位置
+    pub home_x: i32,                  // 出撃するX座標
     pub const_max_explosion_cnt: i32, // 撃破されてから再出撃までのカウント数(定数)
     pub explosion_cnt: Option<i32>,   // Some(再出撃までの残りカウント)
     pub life: i32,
-    life_up: bool,   // スコアボーナスで残機が増加済であれば真
-    sprite: Vec<u8>, // 左側から縦8ピクセルずつを8bitのベクタで表す
+    pub start_life: i32,          // ゲーム開始時の残機
+    pub bonus_score: Option<i32>, // Some(残機が1増える得点)
+    life_up: bool,                // スコアボーナスで残機が増加済であれば真
+    sprite: Vec<u8>,              // 左側から縦8ピクセルずつを8bitのベクタで表す
     explosion_sprite: [Vec<u8>; 2],
-    se: Sound,
-    se_volume: f32, // 発射音の音量(0〜1)
 }
 impl Player {
-    pub fn new(
-        sprite: Vec<u8>,
-        explosion_sprite1: Vec<u8>,
-        explosion_sprite2: Vec<u8>,
-        se: Sound,
-    ) -> Self {
+    pub fn new(sprite: Vec<u8>, explosion_sprite1: Vec<u8>, explosion_sprite2: Vec<u8>) -> Self {
         Player {
             width: sprite.len() as i32,
             pos: IVec2::new(8, canvas::GAME_HEIGHT - 8 * 3),
             pre_pos: IVec2::new(8, canvas::GAME_HEIGHT - 8 * 3),
+            home_x: 8,
             const_max_explosion_cnt: 160,
             explosion_cnt: None,
             life: 3,
+            start_life: 3,
+            bonus_score: Some(1500),
             life_up: false,
             sprite,
             explosion_sprite: [explosion_sprite1, explosion_sprite2],
-            se,
-            se_volume: 0.3,
         }
     }
     pub fn reset_all(&mut self) {
         self.reset_stage();
-        self.life = 3;
+        self.life = self.start_life;
         self.life_up = false;
     }
     pub fn reset_stage(&mut self) {
-        self.pos = IVec2::new(8, canvas::GAME_HEIGHT - 8 * 3);
-        self.pre_pos = IVec2::new(8, canvas::GAME_HEIGHT - 8 * 3);
+        self.pos = IVec2::new(self.home_x, canvas::GAME_HEIGHT - 8 * 3);
+        self.pre_pos = self.pos;
         self.explosion_cnt = None;
     }
-    pub fn set_se_volume(&mut self, volume: i32) {
-        self.se_volume = (volume as f32) / 100.;
+    pub fn width(&self) -> i32 {
+        self.width
     }
-    pub fn update(&mut self, dot_map: &mut DotMap) {
+    pub fn update(&mut self, dot_map: &mut DotMap, input: &Input) {
         self.pre_pos = self.pos;
         // 撃破後、復活前
         if let Some(cnt) = self.explosion_cnt {
@@ -253,7 +253,7 @@ impl Player {
             } else if self.const_max_explosion_cnt < cnt {
                 // 一定時間経過したら復活する
                 self.explosion_cnt = None;
-                self.pos.x = 8;
+                self.pos.x = self.home_x;
                 self.life -= 1;
                 return;
             }
@@ -263,13 +263,11 @@ impl Player {
         }
 
         // プレイヤー移動範囲制限
-        if 7 < self.pos.x && (is_key_down(KeyCode::A) || is_key_down(KeyCode::Left)) {
+        if 7 < self.pos.x && input.left {
             // 左に移動
             self.pos.x -= 1;
         }
-        if self.pos.x + self.width < canvas::GAME_WIDTH - 7
-            && (is_key_down(KeyCode::D) || is_key_down(KeyCode::Right))
-        {
+        if self.pos.x + self.width < canvas::GAME_WIDTH - 7 && input.right {
             // 右に移動
             self.pos.x += 1;
         }
@@ -277,7 +275,7 @@ impl Player {
     }
     // プレイヤーをドットマップに描画(縦方向のバイト境界はまたがない)
     fn draw(&mut self, dot_map: &mut DotMap) {
-        if let Some(_) = self.explosion_cnt {
+        if self.explosion_cnt.is_some() {
             return;
         }
         // 前回描画した部分を0で消す
@@ -285,19 +283,11 @@ impl Player {
         // 移動後描画する
         self.array_sprite(dot_map);
     }
-    pub fn remove(&mut self, dot_map: &mut DotMap) {
+    pub fn remove(&mut self, dot_map: &mut DotMap, se: &mut SoundQueue) {
         self.explosion_cnt = Some(0);
         self.erase(dot_map, self.pos);
         // 爆発音再生
-        // wasmでは再生しない
-        #[cfg(not(target_arch = "wasm32"))]
-        play_sound(
-            self.se,
-            PlaySoundParams {
-                looped: false,
-                volume: self.se_volume,
-            },
-        );
+        se.play(Se::PlayerExplosion);
     }
 }
 
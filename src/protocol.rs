@@ -0,0 +1,414 @@
+use crate::env::{Action, Env, Observation};
+use crate::error::AssetError;
+use crate::game::{Difficulty, Game, Scene};
+use macroquad::prelude::IVec2;
+use std::io::{self, BufRead, Write};
+
+// 標準入出力で外部のプログラムからゲームを操作する
+//
+// 標準入力から1行に1つのJSONオブジェクトを読み、1フレーム進めるごとに状態を1行のJSONで標準出力に書く
+// 入力のキー
+//   "action": 行動の名前(noop, left, right, fire, left_fire, right_fire)、省略するとnoop
+//   "reset": trueならフレームを進めずに新しいゲームを始める
+// 出力のキー
+//   frame, scene, score, reward, done, lives, stage
+//   player, player_bullet, ufo: {"x":X,"y":Y}(画面上に無ければnull)
+//   aliens, alien_bullets: {"x":X,"y":Y}の配列
+//   bitmap: {"width":W,"height":H,"data":BASE64}(bitmapが真のときのみ)
+//     dataは上の行から1ドット1ビット(上位ビットから)で、各行をバイト境界まで0で埋めたもの
+// 入力が不正な場合は{"error":メッセージ}を書いて次の行を待つ
+// 起動直後に最初の状態を1行書き、標準入力が閉じたら終わる
+pub fn run(difficulty: Difficulty, downsample: i32, bitmap: bool) -> Result<(), AssetError> {
+    let mut env = Env::new(difficulty, downsample)?;
+    let observation = env.reset();
+    let mut frame = 0;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut write = |line: String| {
+        // 相手が読むのをやめたら終わる
+        writeln!(out, "{}", line).and_then(|_| out.flush()).is_ok()
+    };
+    let mut line = state_json(env.game(), frame, 0, false, bitmap.then_some(&observation));
+    if !write(line) {
+        return Ok(());
+    }
+    for request in io::stdin().lock().lines() {
+        let Ok(request) = request else {
+            break;
+        };
+        if request.trim().is_empty() {
+            continue;
+        }
+        line = match parse_request(&request) {
+            Ok(Request::Reset) => {
+                let observation = env.reset();
+                frame = 0;
+                state_json(env.game(), frame, 0, false, bitmap.then_some(&observation))
+            }
+            Ok(Request::Step(action)) => {
+                let (observation, reward, done) = env.step(action);
+                frame += 1;
+                state_json(
+                    env.game(),
+                    frame,
+                    reward,
+                    done,
+                    bitmap.then_some(&observation),
+                )
+            }
+            Err(e) => format!("{{\"error\":{}}}", json_string(&e)),
+        };
+        if !write(line) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, Debug)]
+enum Request {
+    Reset,
+    Step(Action),
+}
+
+fn parse_request(text: &str) -> Result<Request, String> {
+    let mut action = Action::Noop;
+    for (key, value) in parse_object(text)? {
+        match (key.as_str(), value) {
+            ("reset", Value::Bool(true)) => return Ok(Request::Reset),
+            ("reset", Value::Bool(false)) => {}
+            ("action", Value::Str(name)) => {
+                action = Action::from_name(&name)
+                    .ok_or_else(|| format!("{}という行動はありません。", name))?;
+            }
+            ("reset" | "action", _) => return Err(format!("{}の値の型が不正です。", key)),
+            _ => return Err(format!("{}というキーはありません。", key)),
+        }
+    }
+    Ok(Request::Step(action))
+}
+
+// 入力で使うJSONの値
+enum Value {
+    Str(String),
+    Bool(bool),
+    Other, // 数値とnull
+}
+
+// 入れ子の無いJSONオブジェクトを読んでキーと値の組を返す
+fn parse_object(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut chars = text.trim().chars().peekable();
+    let invalid = || "JSONオブジェクトとして読めません。".to_owned();
+    if chars.next() != Some('{') {
+        return Err(invalid());
+    }
+    let mut pairs = Vec::new();
+    loop {
+        skip_space(&mut chars);
+        match chars.next() {
+            Some('}') if pairs.is_empty() => break,
+            Some('"') => {}
+            _ => return Err(invalid()),
+        }
+        let key = parse_string(&mut chars).ok_or_else(invalid)?;
+        skip_space(&mut chars);
+        if chars.next() != Some(':') {
+            return Err(invalid());
+        }
+        skip_space(&mut chars);
+        let value = if chars.peek() == Some(&'"') {
+            chars.next();
+            Value::Str(parse_string(&mut chars).ok_or_else(invalid)?)
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ',' || c == '}' || c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            match word.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Other,
+                _ if word.parse::<f64>().is_ok() => Value::Other,
+                _ => return Err(invalid()),
+            }
+        };
+        pairs.push((key, value));
+        skip_space(&mut chars);
+        match chars.next() {
+            Some(',') => {}
+            Some('}') => break,
+            _ => return Err(invalid()),
+        }
+    }
+    skip_space(&mut chars);
+    if chars.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(pairs)
+}
+
+fn skip_space(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+// 開きの「"」の後から閉じの「"」までを読む
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut s = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(s),
+            '\\' => match chars.next()? {
+                'b' => s.push('\u{8}'),
+                'f' => s.push('\u{c}'),
+                'n' => s.push('\n'),
+                'r' => s.push('\r'),
+                't' => s.push('\t'),
+                'u' => {
+                    // 16進数でちょうど4桁
+                    let code: String = chars.by_ref().take(4).collect();
+                    if code.len() != 4 || !code.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return None;
+                    }
+                    s.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => s.push(c),
+            },
+            c => s.push(c),
+        }
+    }
+}
+
+fn state_json(
+    game: &Game,
+    frame: i32,
+    reward: i32,
+    done: bool,
+    observation: Option<&Observation>,
+) -> String {
+    // ゲーム開始前はプレイヤーとエイリアンがまだ配置されていない
+    let started = !matches!(game.scene, Scene::Title | Scene::LaunchGame(_));
+    let player = (started && game.player.explosion_cnt.is_none()).then_some(game.player.pos);
+    let aliens = if started {
+        game.alien.live_alien_pos()
+    } else {
+        Vec::new()
+    };
+    let mut json = format!(
+        "{{\"frame\":{},\"scene\":\"{}\",\"score\":{},\"reward\":{},\"done\":{},\"lives\":{},\"stage\":{}",
+        frame,
+        scene_name(game.scene),
+        game.player_bullet.score,
+        reward,
+        done,
+        game.player.life,
+        game.stage
+    );
+    json += &format!(",\"player\":{}", pos_json(player));
+    json += &format!(
+        ",\"player_bullet\":{}",
+        pos_json(game.player_bullet.live_pos())
+    );
+    json += &format!(",\"ufo\":{}", pos_json(game.ufo.pos()));
+    json += &format!(",\"aliens\":{}", pos_list_json(&aliens));
+    json += &format!(
+        ",\"alien_bullets\":{}",
+        pos_list_json(&game.alien_bullets.live_bullet_pos())
+    );
+    if let Some(observation) = observation {
+        json += &format!(
+            ",\"bitmap\":{{\"width\":{},\"height\":{},\"data\":\"{}\"}}",
+            observation.width,
+            observation.height,
+            base64(&pack_bits(observation))
+        );
+    }
+    json.push('}');
+    json
+}
+
+fn scene_name(scene: Scene) -> &'static str {
+    match scene {
+        Scene::Title => "title",
+        Scene::SelectPlayers(_) => "select_players",
+        Scene::Play => "play",
+        Scene::Pause => "pause",
+        Scene::LaunchGame(_) => "launch_game",
+        Scene::LaunchStage(_) => "launch_stage",
+        Scene::PlayPlayer(_) => "play_player",
+        Scene::ResetStage => "reset_stage",
+        Scene::Gameover(_) => "gameover",
+        Scene::EnterName => "enter_name",
+    }
+}
+
+fn pos_json(pos: Option<IVec2>) -> String {
+    match pos {
+        Some(pos) => format!("{{\"x\":{},\"y\":{}}}", pos.x, pos.y),
+        None => "null".to_owned(),
+    }
+}
+
+fn pos_list_json(list: &[IVec2]) -> String {
+    let items: Vec<String> = list.iter().map(|&pos| pos_json(Some(pos))).collect();
+    format!("[{}]", items.join(","))
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// 1ドット1ビットに詰める(各行はバイト境界まで0で埋める)
+fn pack_bits(observation: &Observation) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for row in observation.dots.chunks(observation.width as usize) {
+        for dots in row.chunks(8) {
+            let byte = dots
+                .iter()
+                .enumerate()
+                .fold(0, |byte, (i, &dot)| byte | (dot << (7 - i)));
+            bytes.push(byte);
+        }
+    }
+    bytes
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_valid_requests() {
+        assert_eq!(parse_request("{}"), Ok(Request::Step(Action::Noop)));
+        assert_eq!(
+            parse_request(r#" { "action" : "left_fire" } "#),
+            Ok(Request::Step(Action::LeftFire))
+        );
+        assert_eq!(parse_request(r#"{"reset":true}"#), Ok(Request::Reset));
+        assert_eq!(
+            parse_request(r#"{"reset":false,"action":"right"}"#),
+            Ok(Request::Step(Action::Right))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_requests() {
+        for text in [
+            "",
+            "[]",
+            "{",
+            r#"{"action":"jump"}"#,
+            r#"{"action":1}"#,
+            r#"{"reset":"yes"}"#,
+            r#"{"speed":2}"#,
+            r#"{"action":"fire",}"#,
+            r#"{"action" "fire"}"#,
+            r#"{"action":"fire"} x"#,
+            r#"{"reset":maybe}"#,
+        ] {
+            assert!(parse_request(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parses_values_of_each_type() {
+        let pairs = parse_object(r#"{"a":"x","b":true,"c":false,"d":null,"e":-1.5e3}"#).unwrap();
+        let keys: Vec<&str> = pairs.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["a", "b", "c", "d", "e"]);
+        assert!(matches!(&pairs[0].1, Value::Str(s) if s == "x"));
+        assert!(matches!(pairs[1].1, Value::Bool(true)));
+        assert!(matches!(pairs[2].1, Value::Bool(false)));
+        assert!(matches!(pairs[3].1, Value::Other));
+        assert!(matches!(pairs[4].1, Value::Other));
+    }
+
+    #[test]
+    fn string_escapes() {
+        let parse = |text: &str| parse_string(&mut text.chars().peekable());
+        // 閉じの「"」が無い
+        assert_eq!(parse("abc"), None);
+        assert_eq!(
+            parse(r#"a\"b\\c\/d\b\f\n\r\t""#).as_deref(),
+            Some("a\"b\\c/d\u{8}\u{c}\n\r\t")
+        );
+        assert_eq!(parse(r#"Aあ""#).as_deref(), Some("Aあ"));
+        assert_eq!(parse(r#"\u00""#), None);
+        assert_eq!(parse(r#"\u+041""#), None);
+        // 対になっていないサロゲートは文字にならない
+        assert_eq!(parse(r#"\ud800""#), None);
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+        // 書き出した文字列を読み直すと元に戻る
+        let text = "tab\there\r\u{8}\u{c}\"\\";
+        let json = json_string(text);
+        assert_eq!(
+            parse_string(&mut json[1..].chars().peekable()).as_deref(),
+            Some(text)
+        );
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"M"), "TQ==");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Many"), "TWFueQ==");
+        assert_eq!(base64(&[0xff, 0xfe, 0xfd]), "//79");
+    }
+
+    #[test]
+    fn pack_bits_pads_each_row() {
+        // 幅10ドットの2行は、各行が2バイトになる
+        let mut dots = vec![0; 20];
+        dots[0] = 1;
+        dots[9] = 1;
+        dots[10 + 1] = 1;
+        dots[10 + 8] = 1;
+        let observation = Observation {
+            width: 10,
+            height: 2,
+            dots,
+        };
+        assert_eq!(
+            pack_bits(&observation),
+            [0b1000_0000, 0b0100_0000, 0b0100_0000, 0b1000_0000]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::canvas;
+use crate::dot_map::{ColorArea, DotMap};
+use crate::palette::Palette;
+use macroquad::prelude::*;
+
+// 1つの表示領域に対応する、使い回すテクスチャ
+struct DotTexture {
+    texture: Texture2D,
+    image: Image,
+    // 最後にテクスチャに転送したドットマップ、爆発中かどうか、色を変えた範囲
+    shown: Option<(Vec<Vec<u8>>, bool, Vec<ColorArea>)>,
+}
+
+impl DotTexture {
+    fn new(width: i32, height: i32) -> Self {
+        let image = Image {
+            bytes: vec![0; (width * height * 4) as usize],
+            width: width as u16,
+            height: height as u16,
+        };
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        DotTexture {
+            texture,
+            image,
+            shown: None,
+        }
+    }
+    // ドットマップが前回から変わっていた場合のみテクスチャを書き換える
+    fn update(
+        &mut self,
+        dots: &[Vec<u8>],
+        player_exploding: bool,
+        areas: &[ColorArea],
+        color_bytes: impl FnOnce() -> Vec<u8>,
+    ) {
+        if let Some((shown_dots, shown_exploding, shown_areas)) = &self.shown {
+            if shown_dots == dots && *shown_exploding == player_exploding && shown_areas == areas {
+                return;
+            }
+        }
+        self.image.bytes = color_bytes();
+        self.texture.update(&self.image);
+        self.shown = Some((dots.to_vec(), player_exploding, areas.to_vec()));
+    }
+    // 指定したドット単位のY座標から画面幅いっぱいに描く
+    fn draw(&self, y: i32) {
+        draw_texture_ex(
+            self.texture,
+            canvas::dot2x(0),
+            canvas::dot2y(y),
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(
+                    canvas::dot2pix(self.image.width as i32),
+                    canvas::dot2pix(self.image.height as i32),
+                )),
+                ..Default::default()
+            },
+        );
+    }
+}
+
+// ドット単位で描く領域をそれぞれのテクスチャに描画する
+pub struct Renderer {
+    textures: Vec<DotTexture>, // 領域ごとのテクスチャ
+    palette: Palette,
+}
+
+impl Renderer {
+    pub fn new(palette: Palette) -> Self {
+        Renderer {
+            textures: Vec::new(),
+            palette,
+        }
+    }
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+    // パレットを変えたら次の描画ですべてのテクスチャを書き換える
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        for texture in self.textures.iter_mut() {
+            texture.shown = None;
+        }
+    }
+    // 各領域を指定したドット単位のY座標から、指定した範囲の色を変えて描く
+    pub fn draw(&mut self, planes: &[(&DotMap, i32, &[ColorArea])], player_exploding: bool) {
+        for (i, &(plane, y, areas)) in planes.iter().enumerate() {
+            let size_changed = self.textures.get(i).is_none_or(|t| {
+                t.image.width as i32 != plane.width() || t.image.height as i32 != plane.height()
+            });
+            if size_changed {
+                let texture = DotTexture::new(plane.width(), plane.height());
+                if let Some(old) = self.textures.get_mut(i) {
+                    // 大きさの変わった古いテクスチャは解放する
+                    old.texture.delete();
+                    *old = texture;
+                } else {
+                    self.textures.push(texture);
+                }
+            }
+            let palette = &self.palette;
+            let texture = &mut self.textures[i];
+            texture.update(&plane.map, player_exploding, areas, || {
+                plane.convert_to_color_bytes(palette, player_exploding, areas)
+            });
+            texture.draw(y);
+        }
+    }
+}
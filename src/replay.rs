@@ -0,0 +1,234 @@
+use crate::error::invalid_data;
+use crate::game::Difficulty;
+use crate::input::Input;
+use std::fs;
+use std::io;
+
+// リプレイファイルの先頭行
+const HEADER: &str = "invader-macroquad replay";
+// リプレイファイルの形式のバージョン(形式を変えたら上げる)
+pub const VERSION: u32 = 7;
+// 最後に遊んだゲームのリプレイの保存先
+pub const LAST_REPLAY_PATH: &str = "last.replay";
+
+// 人数選択からゲーム終了までの全フレームの入力
+pub struct Replay {
+    pub difficulty: Difficulty, // 記録したゲームの難易度
+    frames: Vec<Input>,
+}
+
+impl Replay {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Replay {
+            difficulty,
+            frames: Vec::new(),
+        }
+    }
+    pub fn push(&mut self, input: Input) {
+        self.frames.push(input);
+    }
+    // 1行目にヘッダとバージョン、2行目に難易度、以降1行1フレームで
+    // 左、右、射撃、ポーズ、ステージやり直し、2人目の左、右、射撃、UFO発進の入力を0/1で並べる
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut text = format!(
+            "{} {}\ndifficulty {}\n",
+            HEADER,
+            VERSION,
+            self.difficulty.name()
+        );
+        for input in &self.frames {
+            text += &format!(
+                "{}{}{}{}{}{}{}{}{}\n",
+                input.left as u8,
+                input.right as u8,
+                input.fire as u8,
+                input.pause as u8,
+                input.restart as u8,
+                input.left2 as u8,
+                input.right2 as u8,
+                input.fire2 as u8,
+                input.ufo2 as u8
+            );
+        }
+        fs::write(path, text)
+    }
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        let version = header
+            .strip_prefix(HEADER)
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or_else(|| invalid_data(format!("{}はリプレイファイルではありません。", path)))?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "リプレイファイルのバージョン{}には対応していません。(対応バージョン: {})",
+                version, VERSION
+            )));
+        }
+        let difficulty = lines
+            .next()
+            .and_then(|line| line.strip_prefix("difficulty "))
+            .and_then(|name| Difficulty::from_name(name.trim()))
+            .ok_or_else(|| invalid_data("2行目が不正です。".to_owned()))?;
+        let mut frames = Vec::new();
+        for (i, line) in lines.enumerate() {
+            // ヘッダ行と難易度の行の分、行番号は3から始まる
+            let input = parse_input(line)
+                .ok_or_else(|| invalid_data(format!("{}行目が不正です。", i + 3)))?;
+            frames.push(input);
+        }
+        Ok(Replay { difficulty, frames })
+    }
+}
+
+fn parse_input(line: &str) -> Option<Input> {
+    let keys: Vec<bool> = line
+        .trim_end()
+        .chars()
+        .map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    if keys.len() != 9 {
+        return None;
+    }
+    Some(Input {
+        left: keys[0],
+        right: keys[1],
+        fire: keys[2],
+        pause: keys[3],
+        restart: keys[4],
+        left2: keys[5],
+        right2: keys[6],
+        fire2: keys[7],
+        ufo2: keys[8],
+    })
+}
+
+// 記録された入力を1フレームずつ取り出す
+pub struct Playback {
+    replay: Replay,
+    i: usize, // 次に取り出すフレーム
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback { replay, i: 0 }
+    }
+    // 全フレーム取り出し終わったらNoneを返す
+    pub fn next_input(&mut self) -> Option<Input> {
+        let input = self.replay.frames.get(self.i).copied();
+        self.i += 1;
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::{Bot, GameView, ReferenceBot};
+    use crate::game::{Game, Scene};
+    use crate::high_score::HighScore;
+    use crate::sprite::Sprites;
+    use crate::test_util::temp_path;
+
+    // 入力を与えてタイトル画面に戻るまで遊び、最後の状態を返す
+    fn play(mut next_input: impl FnMut(&Game) -> Option<Input>) -> Game {
+        let sprites = Sprites::load().unwrap();
+        let mut game = Game::new(&sprites, HighScore::default(), Difficulty::Hard);
+        game.select_players();
+        while game.scene != Scene::Title {
+            let Some(input) = next_input(&game) else {
+                break;
+            };
+            game.update(&input);
+            game.take_sound_requests();
+        }
+        game
+    }
+
+    #[test]
+    fn replay_reproduces_the_game() {
+        let mut replay = Replay::new(Difficulty::Hard);
+        let mut bot = ReferenceBot::default();
+        let mut alien_live = Vec::new();
+        let mut score = 0;
+        let recorded = play(|game| {
+            // タイトル画面に戻るとリセットされるので、直前の状態を覚えておく
+            alien_live = game.alien.live().to_vec();
+            score = game.player_bullet.score;
+            let input = bot.input(&GameView::new(game));
+            replay.push(input);
+            Some(input)
+        });
+        assert_eq!(recorded.scene, Scene::Title);
+        assert!(0 < score);
+
+        let path = temp_path("replay_reproduces_the_game.replay");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+        assert_eq!(loaded.frames, replay.frames);
+
+        let mut playback = Playback::new(loaded);
+        let mut replayed_alien_live = Vec::new();
+        let mut replayed_score = 0;
+        let replayed = play(|game| {
+            replayed_alien_live = game.alien.live().to_vec();
+            replayed_score = game.player_bullet.score;
+            playback.next_input()
+        });
+        assert_eq!(replayed.scene, Scene::Title);
+        assert_eq!(replayed_score, score);
+        assert_eq!(replayed_alien_live, alien_live);
+        // 記録した入力をちょうど使い切っている
+        assert!(playback.next_input().is_none());
+    }
+
+    #[test]
+    fn every_key_survives_save_and_load() {
+        let mut replay = Replay::new(Difficulty::Easy);
+        for bits in 0..512u32 {
+            let key = |i: u32| bits & (1 << i) != 0;
+            replay.push(Input {
+                left: key(0),
+                right: key(1),
+                fire: key(2),
+                pause: key(3),
+                restart: key(4),
+                left2: key(5),
+                right2: key(6),
+                fire2: key(7),
+                ufo2: key(8),
+            });
+        }
+        let path = temp_path("every_key_survives_save_and_load.replay");
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::Easy);
+        assert_eq!(loaded.frames, replay.frames);
+    }
+
+    #[test]
+    fn rejects_other_versions_and_broken_lines() {
+        let path = temp_path("rejects_other_versions_and_broken_lines.replay");
+        let cases = [
+            format!("{} {}\ndifficulty normal\n", HEADER, VERSION - 1),
+            "not a replay\n".to_owned(),
+            format!("{} {}\ndifficulty unknown\n", HEADER, VERSION),
+            format!("{} {}\ndifficulty normal\n00000000\n", HEADER, VERSION),
+            format!("{} {}\ndifficulty normal\n00000000x\n", HEADER, VERSION),
+        ];
+        for text in cases {
+            fs::write(&path, &text).unwrap();
+            let e = Replay::load(&path).err().expect(&text);
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData, "{}", text);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use crate::canvas;
+use crate::controls::Controls;
+use crate::error::invalid_data;
+use crate::game::Difficulty;
+use crate::sound::{DEFAULT_GAINS, SE_NAMES};
+use std::fs;
+use std::io;
+
+// 設定の保存先
+pub const SETTINGS_PATH: &str = "settings.txt";
+// ウィンドウの倍率の上限
+pub const MAX_SCALE: i32 = 8;
+
+// 起動をまたいで保存する設定
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Settings {
+    pub volume: i32,     // 全体の音量(0〜100)
+    pub se_volume: i32,  // 効果音の音量(0〜100)
+    pub mute: bool,      // 真なら音を鳴らさない
+    pub gains: [i32; 6], // 効果音ごとの音量(0〜100、sound::SE_NAMESの順)
+    pub palette: String, // パレット名
+    pub scale: i32,      // ウィンドウの倍率
+    pub fullscreen: bool,
+    pub controls: Controls,
+    pub difficulty: Difficulty,
+    pub audio_dir: Option<String>, // 組み込みの効果音の代わりに読み込むWAVファイルのディレクトリ
+    pub synth: bool,               // 真なら効果音をWAVファイルの代わりに起動時に生成する
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 30,
+            se_volume: 100,
+            mute: false,
+            gains: DEFAULT_GAINS,
+            palette: "overlay".to_owned(),
+            scale: canvas::SCALE,
+            fullscreen: false,
+            controls: Controls::default(),
+            difficulty: Difficulty::default(),
+            audio_dir: None,
+            synth: false,
+        }
+    }
+}
+
+impl Settings {
+    // ファイルが無い場合は既定の設定を返す
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+            Err(e) => return Err(e),
+        };
+        let mut settings = Settings::default();
+        // 1行に「項目名 値」
+        // 書かれていない項目は既定の値のまま
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            settings
+                .parse_line(line)
+                .map_err(|e| invalid_data(format!("{}行目が不正です: {}", i + 1, e)))?;
+        }
+        Ok(settings)
+    }
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        match key {
+            "volume" => {
+                let volume: i32 = value
+                    .parse()
+                    .map_err(|_| "音量は数値で指定してください。")?;
+                self.volume = volume.clamp(0, 100);
+            }
+            "se_volume" => {
+                let volume: i32 = value
+                    .parse()
+                    .map_err(|_| "効果音の音量は数値で指定してください。")?;
+                self.se_volume = volume.clamp(0, 100);
+            }
+            "mute" => self.mute = parse_on_off(value, "ミュート")?,
+            "synth" => self.synth = parse_on_off(value, "効果音の生成")?,
+            // 「gain 効果音名 音量」
+            "gain" => {
+                let (name, gain) = value.split_once(' ').unwrap_or((value, ""));
+                let i = SE_NAMES
+                    .iter()
+                    .position(|&n| n == name)
+                    .ok_or_else(|| format!("{}という効果音はありません。", name))?;
+                let gain: i32 = gain
+                    .trim()
+                    .parse()
+                    .map_err(|_| "効果音ごとの音量は数値で指定してください。")?;
+                self.gains[i] = gain.clamp(0, 100);
+            }
+            "palette" => self.palette = value.to_owned(),
+            "scale" => {
+                let scale: i32 = value
+                    .parse()
+                    .map_err(|_| "倍率は数値で指定してください。")?;
+                self.scale = scale.clamp(1, MAX_SCALE);
+            }
+            "fullscreen" => {
+                self.fullscreen = parse_on_off(value, "全画面表示")?;
+            }
+            "difficulty" => {
+                self.difficulty = Difficulty::from_name(value)
+                    .ok_or_else(|| format!("{}という難易度はありません。", value))?;
+            }
+            "key" => self.controls.parse_keys(value)?,
+            "audio_dir" => self.audio_dir = Some(value.to_owned()),
+            _ => return Err(format!("{}という項目はありません。", key)),
+        }
+        Ok(())
+    }
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut text = format!(
+            "volume {}\nse_volume {}\nmute {}\nsynth {}\npalette {}\nscale {}\nfullscreen {}\ndifficulty {}\n",
+            self.volume,
+            self.se_volume,
+            on_off(self.mute),
+            on_off(self.synth),
+            self.palette,
+            self.scale,
+            on_off(self.fullscreen),
+            self.difficulty.name()
+        );
+        for (name, gain) in SE_NAMES.iter().zip(self.gains) {
+            text += &format!("gain {} {}\n", name, gain);
+        }
+        for line in self.controls.to_lines() {
+            text += &format!("key {}\n", line);
+        }
+        if let Some(dir) = &self.audio_dir {
+            text += &format!("audio_dir {}\n", dir);
+        }
+        fs::write(path, text)
+    }
+}
+
+fn parse_on_off(value: &str, item: &str) -> Result<bool, String> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("{}はonまたはoffで指定してください。", item)),
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_path;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut settings = Settings {
+            volume: 70,
+            se_volume: 40,
+            mute: true,
+            palette: "green".to_owned(),
+            scale: 5,
+            fullscreen: true,
+            difficulty: Difficulty::Hard,
+            audio_dir: Some("my audio".to_owned()),
+            synth: true,
+            ..Settings::default()
+        };
+        settings.gains[2] = 15;
+        let path = temp_path("settings.txt");
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), settings);
+    }
+
+    #[test]
+    fn missing_file_gives_default() {
+        let settings = Settings::load(&temp_path("no-such-settings.txt")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn scale_is_clamped() {
+        let mut settings = Settings::default();
+        settings.parse_line("scale 100").unwrap();
+        assert_eq!(settings.scale, MAX_SCALE);
+        settings.parse_line("scale 0").unwrap();
+        assert_eq!(settings.scale, 1);
+    }
+
+    #[test]
+    fn rejects_broken_lines() {
+        let mut settings = Settings::default();
+        for line in ["scale big", "mute yes", "difficulty nightmare", "unknown 1"] {
+            assert!(settings.parse_line(line).is_err(), "{}", line);
+        }
+        // 失敗した行で設定が変わっていないこと
+        assert_eq!(settings, Settings::default());
+    }
+}
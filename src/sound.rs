@@ -0,0 +1,391 @@
+use crate::error::AssetError;
+use crate::settings::Settings;
+use crate::synth::SynthConfig;
+use macroquad::audio::*;
+use std::path::Path;
+
+// 実行ファイルに組み込む効果音(ファイル名から拡張子を除いたもの、WAVデータ)
+const EMBEDDED_AUDIO: [(&str, &[u8]); 9] = [
+    ("shoot", include_bytes!("../audio/shoot.wav")),
+    (
+        "player_explosion",
+        include_bytes!("../audio/player_explosion.wav"),
+    ),
+    ("fastinvader1", include_bytes!("../audio/fastinvader1.wav")),
+    ("fastinvader2", include_bytes!("../audio/fastinvader2.wav")),
+    ("fastinvader3", include_bytes!("../audio/fastinvader3.wav")),
+    ("fastinvader4", include_bytes!("../audio/fastinvader4.wav")),
+    (
+        "invader_killed",
+        include_bytes!("../audio/invader_killed.wav"),
+    ),
+    ("ufo_flying", include_bytes!("../audio/ufo_flying.wav")),
+    (
+        "ufo_explosion",
+        include_bytes!("../audio/ufo_explosion.wav"),
+    ),
+];
+
+// 効果音の種類
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Se {
+    Shoot,              // プレイヤーの弾の発射音
+    PlayerExplosion,    // プレイヤーの爆発音
+    InvaderStep(usize), // エイリアンの侵攻音(0..4)
+    InvaderKilled,      // エイリアンの爆発音
+    UfoFlying,          // UFOの飛行音
+    UfoExplosion,       // UFOの爆発音
+}
+
+// ゲーム本体から発行される音の再生要求
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundRequest {
+    Play(Se),     // 1回再生
+    PlayLoop(Se), // ループ再生
+    Stop(Se),     // 停止
+}
+
+// 1フレーム分の再生要求をためておく
+#[derive(Default)]
+pub struct SoundQueue {
+    requests: Vec<SoundRequest>,
+}
+
+impl SoundQueue {
+    pub fn play(&mut self, se: Se) {
+        self.requests.push(SoundRequest::Play(se));
+    }
+    pub fn play_loop(&mut self, se: Se) {
+        self.requests.push(SoundRequest::PlayLoop(se));
+    }
+    pub fn stop(&mut self, se: Se) {
+        self.requests.push(SoundRequest::Stop(se));
+    }
+    // ためた要求をすべて取り出す
+    pub fn drain(&mut self) -> Vec<SoundRequest> {
+        std::mem::take(&mut self.requests)
+    }
+}
+
+// 個別に音量を調整できる効果音の名前(Se::index()の順)
+pub const SE_NAMES: [&str; 6] = [
+    "shoot",
+    "player_explosion",
+    "invader_step",
+    "invader_killed",
+    "ufo_flying",
+    "ufo_explosion",
+];
+// 効果音ごとの音量の既定値(%、SE_NAMESの順)
+// UFOの音源が大きいので少し下げる
+pub const DEFAULT_GAINS: [i32; 6] = [100, 100, 100, 100, 30, 30];
+
+impl Se {
+    pub const ALL: [Se; 9] = [
+        Se::Shoot,
+        Se::PlayerExplosion,
+        Se::InvaderStep(0),
+        Se::InvaderStep(1),
+        Se::InvaderStep(2),
+        Se::InvaderStep(3),
+        Se::InvaderKilled,
+        Se::UfoFlying,
+        Se::UfoExplosion,
+    ];
+    // SE_NAMESでの位置(侵攻音の4音は同じ位置)
+    fn index(self) -> usize {
+        match self {
+            Se::Shoot => 0,
+            Se::PlayerExplosion => 1,
+            Se::InvaderStep(_) => 2,
+            Se::InvaderKilled => 3,
+            Se::UfoFlying => 4,
+            Se::UfoExplosion => 5,
+        }
+    }
+}
+
+// 実際に音を鳴らす部分
+pub trait AudioBackend {
+    fn play(&mut self, se: Se, looped: bool, volume: f32);
+    fn stop(&mut self, se: Se);
+    // 再生中の音の音量を変える
+    fn set_volume(&mut self, se: Se, volume: f32);
+}
+
+// macroquadで音を鳴らす
+pub struct MacroquadBackend {
+    shoot: Sound,
+    player_explosion: Sound,
+    invader_step: Vec<Sound>,
+    invader_killed: Sound,
+    ufo_flying: Sound,
+    ufo_explosion: Sound,
+}
+
+impl MacroquadBackend {
+    // 組み込みの効果音を読み込む(synthを指定した場合は代わりにその音色で生成する)
+    // audio_dirを指定した場合、そのディレクトリに<名前>.wavがある効果音はファイルから読み込む
+    pub async fn load(
+        audio_dir: Option<&str>,
+        synth: Option<&SynthConfig>,
+    ) -> Result<Self, AssetError> {
+        let mut sounds = Vec::with_capacity(EMBEDDED_AUDIO.len());
+        for (name, data) in EMBEDDED_AUDIO {
+            let synthesized = synth.and_then(|synth| synth.wav(name));
+            let data = synthesized.as_deref().unwrap_or(data);
+            sounds.push(load_se(audio_dir, name, data).await?);
+        }
+        Ok(MacroquadBackend {
+            shoot: sounds[0],
+            player_explosion: sounds[1],
+            invader_step: sounds[2..6].to_vec(),
+            invader_killed: sounds[6],
+            ufo_flying: sounds[7],
+            ufo_explosion: sounds[8],
+        })
+    }
+    fn se2sound(&self, se: Se) -> Sound {
+        match se {
+            Se::Shoot => self.shoot,
+            Se::PlayerExplosion => self.player_explosion,
+            Se::InvaderStep(i) => self.invader_step[i],
+            Se::InvaderKilled => self.invader_killed,
+            Se::UfoFlying => self.ufo_flying,
+            Se::UfoExplosion => self.ufo_explosion,
+        }
+    }
+}
+
+impl AudioBackend for MacroquadBackend {
+    // wasmでは再生しない
+    #[cfg(target_arch = "wasm32")]
+    fn play(&mut self, _se: Se, _looped: bool, _volume: f32) {}
+    #[cfg(not(target_arch = "wasm32"))]
+    fn play(&mut self, se: Se, looped: bool, volume: f32) {
+        play_sound(self.se2sound(se), PlaySoundParams { looped, volume });
+    }
+    fn stop(&mut self, se: Se) {
+        stop_sound(self.se2sound(se));
+    }
+    fn set_volume(&mut self, se: Se, volume: f32) {
+        set_sound_volume(self.se2sound(se), volume);
+    }
+}
+
+// 何も鳴らさない(ウィンドウを使わずに動かすとき用)
+pub struct SilentBackend;
+
+impl AudioBackend for SilentBackend {
+    fn play(&mut self, _se: Se, _looped: bool, _volume: f32) {}
+    fn stop(&mut self, _se: Se) {}
+    fn set_volume(&mut self, _se: Se, _volume: f32) {}
+}
+
+// 再生要求を受けて、全体の音量、効果音の音量、ミュート、効果音ごとの音量を掛けて鳴らす
+pub struct AudioManager {
+    backend: Box<dyn AudioBackend>,
+    master: f32,      // 全体の音量(0〜1)
+    effects: f32,     // 効果音の音量(0〜1)
+    muted: bool,      // 真なら鳴らさない
+    gains: [f32; 6],  // 効果音ごとの音量(SE_NAMESの順)
+    looping: Vec<Se>, // ループ再生中の音
+}
+
+impl AudioManager {
+    pub fn new(backend: Box<dyn AudioBackend>) -> Self {
+        AudioManager {
+            backend,
+            master: 0.3,
+            effects: 1.,
+            muted: false,
+            gains: DEFAULT_GAINS.map(|gain| gain as f32 / 100.),
+            looping: Vec::new(),
+        }
+    }
+    pub fn silent() -> Self {
+        Self::new(Box::new(SilentBackend))
+    }
+    // 設定の音量とミュートを反映する(ループ再生中の音にも反映する)
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let master = settings.volume as f32 / 100.;
+        let effects = settings.se_volume as f32 / 100.;
+        let gains = settings.gains.map(|gain| gain as f32 / 100.);
+        if (master, effects, settings.mute, gains)
+            == (self.master, self.effects, self.muted, self.gains)
+        {
+            return;
+        }
+        self.master = master;
+        self.effects = effects;
+        self.muted = settings.mute;
+        self.gains = gains;
+        for &se in &self.looping {
+            self.backend.set_volume(se, self.volume(se));
+        }
+    }
+    pub fn request(&mut self, request: SoundRequest) {
+        match request {
+            SoundRequest::Play(se) => self.backend.play(se, false, self.volume(se)),
+            SoundRequest::PlayLoop(se) => {
+                self.backend.play(se, true, self.volume(se));
+                if !self.looping.contains(&se) {
+                    self.looping.push(se);
+                }
+            }
+            SoundRequest::Stop(se) => {
+                self.backend.stop(se);
+                self.looping.retain(|&s| s != se);
+            }
+        }
+    }
+    // 再生中の音をすべて止める
+    pub fn stop_all(&mut self) {
+        for se in Se::ALL {
+            self.backend.stop(se);
+        }
+        self.looping.clear();
+    }
+    fn volume(&self, se: Se) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.master * self.effects * self.gains[se.index()]
+        }
+    }
+}
+
+async fn load_se(audio_dir: Option<&str>, name: &str, data: &[u8]) -> Result<Sound, AssetError> {
+    if let Some(dir) = audio_dir {
+        let path = format!("{}/{}.wav", dir, name);
+        if Path::new(&path).exists() {
+            return load_sound(&path)
+                .await
+                .map_err(|e| AssetError::MissingAudio {
+                    path,
+                    reason: e.to_string(),
+                });
+        }
+    }
+    load_sound_from_bytes(data)
+        .await
+        .map_err(|e| AssetError::MissingAudio {
+            path: format!("(組み込み){}.wav", name),
+            reason: e.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // バックエンドに渡された呼び出し
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Call {
+        Play(Se, bool, f32),
+        Stop(Se),
+        SetVolume(Se, f32),
+    }
+
+    // 呼び出しを記録するだけのバックエンド
+    struct RecordingBackend(Rc<RefCell<Vec<Call>>>);
+
+    impl AudioBackend for RecordingBackend {
+        fn play(&mut self, se: Se, looped: bool, volume: f32) {
+            self.0.borrow_mut().push(Call::Play(se, looped, volume));
+        }
+        fn stop(&mut self, se: Se) {
+            self.0.borrow_mut().push(Call::Stop(se));
+        }
+        fn set_volume(&mut self, se: Se, volume: f32) {
+            self.0.borrow_mut().push(Call::SetVolume(se, volume));
+        }
+    }
+
+    fn recording() -> (AudioManager, Rc<RefCell<Vec<Call>>>) {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let manager = AudioManager::new(Box::new(RecordingBackend(calls.clone())));
+        (manager, calls)
+    }
+    // 記録した呼び出しを取り出す(音量は丸め誤差を除くため千分率にする)
+    fn take(calls: &Rc<RefCell<Vec<Call>>>) -> Vec<Call> {
+        let round = |volume: f32| (volume * 1000.).round() / 1000.;
+        calls
+            .borrow_mut()
+            .drain(..)
+            .map(|call| match call {
+                Call::Play(se, looped, volume) => Call::Play(se, looped, round(volume)),
+                Call::SetVolume(se, volume) => Call::SetVolume(se, round(volume)),
+                call => call,
+            })
+            .collect()
+    }
+    fn settings(volume: i32, se_volume: i32, mute: bool) -> Settings {
+        Settings {
+            volume,
+            se_volume,
+            mute,
+            ..Settings::default()
+        }
+    }
+
+    #[test]
+    fn volume_is_master_times_effects_times_gain() {
+        let (mut manager, calls) = recording();
+        let mut settings = settings(50, 40, false);
+        settings.gains[Se::UfoExplosion.index()] = 25;
+        manager.apply_settings(&settings);
+        manager.request(SoundRequest::Play(Se::Shoot));
+        manager.request(SoundRequest::Play(Se::InvaderStep(3)));
+        manager.request(SoundRequest::Play(Se::UfoExplosion));
+        assert_eq!(
+            take(&calls),
+            [
+                Call::Play(Se::Shoot, false, 0.2),
+                Call::Play(Se::InvaderStep(3), false, 0.2),
+                Call::Play(Se::UfoExplosion, false, 0.05),
+            ]
+        );
+    }
+
+    #[test]
+    fn mute_plays_at_zero_volume() {
+        let (mut manager, calls) = recording();
+        manager.apply_settings(&settings(100, 100, true));
+        manager.request(SoundRequest::Play(Se::Shoot));
+        manager.request(SoundRequest::PlayLoop(Se::UfoFlying));
+        assert_eq!(
+            take(&calls),
+            [
+                Call::Play(Se::Shoot, false, 0.),
+                Call::Play(Se::UfoFlying, true, 0.),
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_change_revolumes_only_looping_sounds() {
+        let (mut manager, calls) = recording();
+        manager.apply_settings(&settings(100, 100, false));
+        manager.request(SoundRequest::Play(Se::Shoot));
+        manager.request(SoundRequest::PlayLoop(Se::UfoFlying));
+        manager.request(SoundRequest::PlayLoop(Se::PlayerExplosion));
+        manager.request(SoundRequest::Stop(Se::PlayerExplosion));
+        take(&calls);
+
+        manager.apply_settings(&settings(50, 100, false));
+        assert_eq!(take(&calls), [Call::SetVolume(Se::UfoFlying, 0.15)]);
+        // 変更が無ければ何もしない
+        manager.apply_settings(&settings(50, 100, false));
+        assert_eq!(take(&calls), []);
+        manager.apply_settings(&settings(50, 100, true));
+        assert_eq!(take(&calls), [Call::SetVolume(Se::UfoFlying, 0.)]);
+
+        manager.stop_all();
+        take(&calls);
+        manager.apply_settings(&settings(100, 100, false));
+        assert_eq!(take(&calls), []);
+    }
+}
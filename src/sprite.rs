@@ -1,3 +1,10 @@
+use crate::error::{AssetError, ShapeError};
+use std::fs;
+use std::io;
+
+// スプライトのファイルを置くディレクトリ
+pub const SPRITE_DIR: &str = "sprites";
+
 pub struct DotShape {
     pub width: i32,              // 幅[ドット]
     pub height: i32,             // 高さ[ドット]
@@ -6,59 +13,201 @@ pub struct DotShape {
 
 impl DotShape {
     // 真偽値で表されたスプライトを時計回りに90度回転させたVec<u8>に変換
-    pub fn create_dot_map(&self) -> Vec<u8> {
+    pub fn create_dot_map(&self) -> Result<Vec<u8>, ShapeError> {
+        if self.height != 8 && self.height != 16 {
+            return Err(ShapeError::BadHeight(self.height));
+        }
         // 指定されたサイズと実際のドットマップのサイズが一致しているか確認
         if self.height as usize != self.dot_map.len() {
-            panic!("指定されたスプライトの高さが実際のデータと異なります。");
+            return Err(ShapeError::HeightMismatch {
+                expected: self.height,
+                actual: self.dot_map.len() as i32,
+            });
         }
         if self.width as usize != self.dot_map[0].len() {
-            panic!("指定されたスプライトの幅が実際のデータと異なります。");
+            return Err(ShapeError::WidthMismatch {
+                expected: self.width,
+                actual: self.dot_map[0].len() as i32,
+            });
         }
         // ドットマップの幅が異なる行が無いか確認
         let map_width = self.dot_map[0].len();
-        for l in &self.dot_map {
-            if l.len() != map_width {
-                panic!("スプライトの形が不正です。");
-            }
+        if let Some(row) = self.dot_map.iter().position(|l| l.len() != map_width) {
+            return Err(ShapeError::RaggedRows { row });
         }
         // 1列8ピクセルを8bitで表す
         // 元のboolの二次元配列に対し時計回りに90度回転させる
-        let mut bytes: Vec<u8>;
-        if self.height == 8 {
-            bytes = vec![0; self.width as usize];
-            for (y, line) in self.dot_map.iter().enumerate() {
-                for (x, dot) in line.iter().enumerate() {
-                    if *dot {
-                        bytes[x] |= 1 << y;
-                    }
-                }
-            }
-        } else if self.height == 16 {
-            // トーチカの場合
-            bytes = vec![0; (self.width * 2) as usize];
-            for y in 0..=7 {
-                for x in 0..self.width as usize {
-                    if self.dot_map[y][x] {
-                        bytes[x] |= 1 << y;
-                    }
+        // 高さ16(トーチカ)の場合は上8ドット、下8ドットの順に並べる
+        let mut bytes = vec![0; (self.width * self.height / 8) as usize];
+        for (y, line) in self.dot_map.iter().enumerate() {
+            let offset = (y / 8) * self.width as usize;
+            for (x, dot) in line.iter().enumerate() {
+                if *dot {
+                    bytes[offset + x] |= 1 << (y % 8);
                 }
             }
-            for y in 8..16 {
-                for x in 0..self.width as usize {
-                    if self.dot_map[y][x] {
-                        bytes[x + self.width as usize] |= 1 << (y - 8);
-                    }
-                }
-            }
-        } else {
-            panic!("高さは8または16でなければなりません。");
         }
-        bytes
+        Ok(bytes)
+    }
+}
+
+// ゲームで使うスプライト
+// ファイルの読み込みと確認は起動時に一度だけ行い、ゲームを作り直すときはこれを複製して使う
+#[derive(Clone)]
+pub struct Sprites {
+    pub player: Vec<u8>,
+    pub player_explosion: [Vec<u8>; 2],
+    pub player_bullet: Vec<u8>,
+    pub player_bullet_explosion: Vec<u8>,
+    pub ufo: Vec<u8>,
+    pub ufo_explosion: Vec<u8>,
+    pub octopus: [Vec<u8>; 2], // 下2列のエイリアン
+    pub crab: [Vec<u8>; 2],    // 中2列のエイリアン
+    pub squid: [Vec<u8>; 2],   // 上1列のエイリアン
+    pub alien_explosion: Vec<u8>,
+    pub alien_bullet_explosion: Vec<u8>,
+    pub shield: Vec<u8>, // 上8ドット、下8ドットの順
+    pub shield_width: usize,
+    pub font: Vec<Vec<u8>>, // 0〜9、A〜Z、記号
+}
+
+impl Sprites {
+    pub fn load() -> Result<Self, AssetError> {
+        let player_bullet = load_sprite("bullet_player")?;
+        if player_bullet.len() != 1 {
+            // プレイヤーの弾の幅は1以外は不正
+            return Err(AssetError::InvalidSprite {
+                name: "bullet_player".to_owned(),
+                source: ShapeError::WidthMismatch {
+                    expected: 1,
+                    actual: player_bullet.len() as i32,
+                },
+            });
+        }
+        let shield_data = ret_dot_data("shield")?;
+        if shield_data.height != 16 {
+            // シールドは上下2段で配置するため高さ16のみ
+            return Err(AssetError::InvalidSprite {
+                name: "shield".to_owned(),
+                source: ShapeError::HeightMismatch {
+                    expected: 16,
+                    actual: shield_data.height,
+                },
+            });
+        }
+        let shield = shield_data
+            .create_dot_map()
+            .map_err(|source| AssetError::InvalidSprite {
+                name: "shield".to_owned(),
+                source,
+            })?;
+        Ok(Sprites {
+            player: load_sprite("player")?,
+            player_explosion: [
+                load_sprite("player_explosion_1")?,
+                load_sprite("player_explosion_2")?,
+            ],
+            player_bullet,
+            player_bullet_explosion: load_sprite("player_bullet_explosion")?,
+            ufo: load_sprite("ufo")?,
+            ufo_explosion: load_sprite("ufo_explosion")?,
+            octopus: [load_sprite("octopus_open")?, load_sprite("octopus_close")?],
+            crab: [load_sprite("crab_banzai")?, load_sprite("crab_down")?],
+            squid: [load_sprite("squid_open")?, load_sprite("squid_close")?],
+            alien_explosion: load_sprite("alien_explosion")?,
+            alien_bullet_explosion: load_sprite("alien_bullet_explosion")?,
+            shield,
+            shield_width: shield_data.width as usize,
+            font: load_font()?,
+        })
+    }
+}
+
+// 名前で指定したスプライトを、時計回りに90度回転させたVec<u8>で返す
+fn load_sprite(name: &str) -> Result<Vec<u8>, AssetError> {
+    ret_dot_data(name)?
+        .create_dot_map()
+        .map_err(|source| AssetError::InvalidSprite {
+            name: name.to_owned(),
+            source,
+        })
+}
+
+// 0〜9、A〜Z、記号のスプライトをchar_dot_dataの順に返す
+fn load_font() -> Result<Vec<Vec<u8>>, AssetError> {
+    char_dot_data()
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            c.create_dot_map()
+                .map_err(|source| AssetError::InvalidSprite {
+                    name: format!("文字{}番", i),
+                    source,
+                })
+        })
+        .collect()
+}
+
+// SPRITE_DIR/<name>.txtがあればそのファイルから、無ければ組み込みのドットデータを返す
+// ファイルのスプライトは組み込みのものと同じ大きさでなければならない
+pub fn ret_dot_data(name: &str) -> Result<DotShape, AssetError> {
+    let builtin =
+        builtin_dot_data(name).ok_or_else(|| AssetError::UnknownSprite(name.to_owned()))?;
+    let path = format!("{}/{}.txt", SPRITE_DIR, name);
+    match load_dot_file(&path) {
+        Ok(shape) => match check_size(&shape, &builtin) {
+            Ok(()) => Ok(shape),
+            Err(source) => Err(AssetError::InvalidSprite { name: path, source }),
+        },
+        Err(e) if is_unavailable(&e) => Ok(builtin),
+        Err(source) => Err(AssetError::SpriteFile { path, source }),
     }
 }
 
+// ファイルが無いか、ファイルを読めない環境(wasmではUnsupportedになる)なら真
+fn is_unavailable(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::Unsupported
+    )
+}
+
+// 差し替えるスプライトの大きさが組み込みのものと同じか確認する
+fn check_size(shape: &DotShape, builtin: &DotShape) -> Result<(), ShapeError> {
+    if shape.height != builtin.height {
+        return Err(ShapeError::HeightMismatch {
+            expected: builtin.height,
+            actual: shape.height,
+        });
+    }
+    if shape.width != builtin.width {
+        return Err(ShapeError::WidthMismatch {
+            expected: builtin.width,
+            actual: shape.width,
+        });
+    }
+    Ok(())
+}
+
+// 組み込みのドットデータと同じく、描画部分を#、非描画部分を_で表したファイルを読み込む
+// 空行と//で始まる行は無視する
+fn load_dot_file(path: &str) -> io::Result<DotShape> {
+    let text = fs::read_to_string(path)?;
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with("//"))
+        .collect();
+    let dot_map = convert_dot_map(lines);
+    Ok(DotShape {
+        width: dot_map.first().map_or(0, |l| l.len()) as i32,
+        height: dot_map.len() as i32,
+        dot_map,
+    })
+}
+
 // ドットデータを変更する際はこの中身のみ変更する
-pub fn ret_dot_data(name: &str) -> DotShape {
+fn builtin_dot_data(name: &str) -> Option<DotShape> {
     let player = DotShape {
         width: 16,
         height: 8,
@@ -292,7 +441,7 @@ pub fn ret_dot_data(name: &str) -> DotShape {
             "_ _ _ # _ _ _ # _ _ _ # _ _ _ _ # _ _ _ _ _ _ _",
         ]),
     };
-    match name {
+    let shape = match name {
         "player" => player,
         "bullet_player" => bullet_player,
         "crab_down" => crab_down,
@@ -309,166 +458,701 @@ pub fn ret_dot_data(name: &str) -> DotShape {
         "ufo_explosion" => ufo_explosion,
         "player_explosion_1" => player_explosion_1,
         "player_explosion_2" => player_explosion_2,
-        _ => panic!(
-            "{}のドットマップ取得に失敗しました。プログラムを終了します。",
-            name
-        ), // ドットマップ取得失敗
-    }
+        _ => return None, // ドットマップ取得失敗
+    };
+    Some(shape)
 }
-// 文字サイズ(8bit x 8bit)のドットマップを返す
+// 文字サイズ(8bit x 8bit)のドットマップを0〜9、A〜Z、SYMBOLSの順に返す
 pub fn char_dot_data() -> Vec<DotShape> {
-    let mut num = Vec::new();
-    // 0
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ # _ _ # # _ _",
-            "_ # _ # _ # _ _",
-            "_ # # _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 1
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ # # _ _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 2
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ _ _ _ # _ _",
-            "_ _ _ # # _ _ _",
-            "_ _ # _ _ _ _ _",
-            "_ # _ _ _ _ _ _",
-            "_ # # # # # _ _",
-        ]),
-    });
-    // 3
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ # # # # # _ _",
-            "_ _ _ _ _ # _ _",
-            "_ _ _ _ # _ _ _",
-            "_ _ _ # # _ _ _",
-            "_ _ _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 4
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ _ _ # _ _ _",
-            "_ _ _ # # _ _ _",
-            "_ _ # _ # _ _ _",
-            "_ # _ _ # _ _ _",
-            "_ # # # # # _ _",
-            "_ _ _ _ # _ _ _",
-            "_ _ _ _ # _ _ _",
-        ]),
-    });
-    // 5
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ # # # # # _ _",
-            "_ # _ _ _ _ _ _",
-            "_ # # # # _ _ _",
-            "_ _ _ _ _ # _ _",
-            "_ _ _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 6
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ _ # # # _ _",
-            "_ _ # _ _ _ _ _",
-            "_ # _ _ _ _ _ _",
-            "_ # # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 7
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ # # # # # _ _",
-            "_ _ _ _ _ # _ _",
-            "_ _ _ _ # _ _ _",
-            "_ _ _ # _ _ _ _",
-            "_ _ # _ _ _ _ _",
-            "_ _ # _ _ _ _ _",
-            "_ _ # _ _ _ _ _",
-        ]),
-    });
-    // 8
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # _ _ _",
-        ]),
-    });
-    // 9
-    num.push(DotShape {
-        width: 8,
-        height: 8,
-        dot_map: convert_dot_map(vec![
-            "_ _ _ _ _ _ _ _",
-            "_ _ # # # _ _ _",
-            "_ # _ _ _ # _ _",
-            "_ # _ _ _ # _ _",
-            "_ _ # # # # _ _",
-            "_ _ _ _ _ # _ _",
-            "_ _ _ _ # _ _ _",
-            "_ # # # _ _ _ _",
-        ]),
-    });
-    num
+    vec![
+        // 0
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ # # _ _",
+                "_ # _ # _ # _ _",
+                "_ # # _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 1
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 2
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ # # _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # # _ _",
+            ]),
+        },
+        // 3
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # # _ _ _",
+                "_ _ _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 4
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # # _ _ _",
+                "_ _ # _ # _ _ _",
+                "_ # _ _ # _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ _ # _ _ _",
+            ]),
+        },
+        // 5
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 6
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # # # _ _",
+                "_ _ # _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 7
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ _ # _ _ _ _ _",
+            ]),
+        },
+        // 8
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // 9
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ # # # _ _ _ _",
+            ]),
+        },
+        // A
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // B
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # _ _ _",
+            ]),
+        },
+        // C
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // D
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # _ _ _",
+            ]),
+        },
+        // E
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # # _ _",
+            ]),
+        },
+        // F
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+            ]),
+        },
+        // G
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ # # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # # _ _",
+            ]),
+        },
+        // H
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // I
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // J
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // K
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ # _ _ _",
+                "_ # _ # _ _ _ _",
+                "_ # # _ _ _ _ _",
+                "_ # _ # _ _ _ _",
+                "_ # _ _ # _ _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // L
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # # _ _",
+            ]),
+        },
+        // M
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # _ # # _ _",
+                "_ # _ # _ # _ _",
+                "_ # _ # _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // N
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # _ _ # _ _",
+                "_ # _ # _ # _ _",
+                "_ # _ _ # # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // O
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // P
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+            ]),
+        },
+        // Q
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ # _ # _ _",
+                "_ # _ _ # _ _ _",
+                "_ _ # # _ # _ _",
+            ]),
+        },
+        // R
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # # # # _ _ _",
+                "_ # _ # _ _ _ _",
+                "_ # _ _ # _ _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // S
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ _ _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // T
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // U
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # # # _ _ _",
+            ]),
+        },
+        // V
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // W
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ # _ # _ _",
+                "_ # _ # _ # _ _",
+                "_ # # _ # # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // X
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # _ # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+            ]),
+        },
+        // Y
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ # _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // Z
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ # # # # # _ _",
+            ]),
+        },
+        // <
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ # _ _ _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ # _ _ _",
+            ]),
+        },
+        // >
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ # _ _ _ _ _",
+            ]),
+        },
+        // -
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+            ]),
+        },
+        // =
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ # # # # # _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+            ]),
+        },
+        // ?
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ _ _ # _ _",
+                "_ _ _ _ _ # _ _",
+                "_ _ _ _ # _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // *
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ # _ # _ # _ _",
+                "_ _ # # # _ _ _",
+                "_ # # # # # _ _",
+                "_ _ # # # _ _ _",
+                "_ # _ # _ # _ _",
+                "_ _ _ _ _ _ _ _",
+            ]),
+        },
+        // .
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // !
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+            ]),
+        },
+        // :
+        DotShape {
+            width: 8,
+            height: 8,
+            dot_map: convert_dot_map(vec![
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+                "_ _ _ # _ _ _ _",
+                "_ _ _ _ _ _ _ _",
+            ]),
+        },
+    ]
+}
+
+// char_dot_dataに含まれる記号
+const SYMBOLS: &str = "<>-=?*.!:";
+
+// 文字に対応するchar_dot_dataのインデックス番号を返す(対応する文字が無ければNone)
+pub fn char2index(c: char) -> Option<usize> {
+    match c {
+        '0'..='9' => Some(c as usize - '0' as usize),
+        'A'..='Z' => Some(c as usize - 'A' as usize + 10),
+        _ => SYMBOLS.chars().position(|s| s == c).map(|i| i + 36),
+    }
 }
 
 // 描画部分を真、非描画部分を偽とするドットマップを返す
@@ -489,3 +1173,70 @@ fn convert_dot_map(dot_map: Vec<&str>) -> Vec<Vec<bool>> {
     }
     bool_map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(width: i32, height: i32) -> DotShape {
+        DotShape {
+            width,
+            height,
+            dot_map: vec![vec![false; width as usize]; height as usize],
+        }
+    }
+
+    #[test]
+    fn override_must_match_builtin_size() {
+        let builtin = builtin_dot_data("crab_banzai").unwrap();
+        let (width, height) = (builtin.width, builtin.height);
+        assert!(check_size(&shape(width, height), &builtin).is_ok());
+        assert!(matches!(
+            check_size(&shape(60, height), &builtin),
+            Err(ShapeError::WidthMismatch { actual: 60, .. })
+        ));
+        assert!(matches!(
+            check_size(&shape(width, 16), &builtin),
+            Err(ShapeError::HeightMismatch { actual: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn every_builtin_sprite_loads() {
+        for name in [
+            "player",
+            "bullet_player",
+            "player_explosion_1",
+            "player_explosion_2",
+            "player_bullet_explosion",
+            "octopus_open",
+            "octopus_close",
+            "crab_banzai",
+            "crab_down",
+            "squid_open",
+            "squid_close",
+            "alien_explosion",
+            "alien_bullet_explosion",
+            "shield",
+            "ufo",
+            "ufo_explosion",
+        ] {
+            let shape = builtin_dot_data(name).unwrap();
+            assert!(shape.create_dot_map().is_ok(), "{}", name);
+        }
+        assert!(matches!(
+            ret_dot_data("no_such_sprite"),
+            Err(AssetError::UnknownSprite(_))
+        ));
+    }
+
+    #[test]
+    fn unreadable_override_falls_back_to_builtin() {
+        // wasmではファイルの読み込みがUnsupportedで失敗する
+        assert!(is_unavailable(&io::Error::from(io::ErrorKind::Unsupported)));
+        assert!(is_unavailable(&io::Error::from(io::ErrorKind::NotFound)));
+        assert!(!is_unavailable(&io::Error::from(
+            io::ErrorKind::PermissionDenied
+        )));
+    }
+}
@@ -0,0 +1,322 @@
+use std::f32::consts::TAU;
+use std::fs;
+use std::io;
+
+// 音色の設定ファイル
+pub const SYNTH_PATH: &str = "synth.txt";
+// 生成する音声のサンプリング周波数(macroquadが変換せずに使える値)
+const SAMPLE_RATE: u32 = 44100;
+// 長さ、アタック、リリースの上限[ミリ秒]
+const MAX_LENGTH: f32 = 5000.;
+// 周波数の上限[Hz](サンプリング周波数の半分)
+const MAX_PITCH: f32 = (SAMPLE_RATE / 2) as f32;
+
+// 波形
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Wave {
+    Square,
+    Triangle,
+    Noise,
+}
+
+impl Wave {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(Wave::Square),
+            "triangle" => Some(Wave::Triangle),
+            "noise" => Some(Wave::Noise),
+            _ => None,
+        }
+    }
+}
+
+// 1つの効果音の音色
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Voice {
+    pub wave: Wave,
+    pub pitch: f32,         // 開始時の周波数[Hz](ノイズは値を変える頻度)
+    pub pitch_end: f32,     // 終了時の周波数[Hz](開始時から直線的に変える)
+    pub length: f32,        // 長さ[ミリ秒]
+    pub attack: f32,        // 鳴り始めてから最大の音量になるまで[ミリ秒]
+    pub release: f32,       // 最大の音量から鳴り終わるまで[ミリ秒]
+    pub vibrato: f32,       // 周波数を揺らす速さ[Hz]
+    pub vibrato_depth: f32, // 周波数を揺らす幅[Hz]
+    pub volume: f32,        // 音量(0〜1)
+}
+
+impl Voice {
+    const fn new(wave: Wave, pitch: f32, pitch_end: f32, length: f32, release: f32) -> Self {
+        Voice {
+            wave,
+            pitch,
+            pitch_end,
+            length,
+            attack: 0.,
+            release,
+            vibrato: 0.,
+            vibrato_depth: 0.,
+            volume: 0.5,
+        }
+    }
+    // 16ビットの音声データを生成する
+    fn samples(&self) -> Vec<i16> {
+        let len = (self.length / 1000. * SAMPLE_RATE as f32).max(1.) as usize;
+        let mut samples = Vec::with_capacity(len);
+        let mut phase = 0f32;
+        // ノイズは線形合同法で作る(毎回同じ音になるよう種は固定)
+        let mut seed = 1u32;
+        let mut noise = 0f32;
+        for i in 0..len {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let progress = i as f32 / len as f32;
+            let freq = self.pitch
+                + (self.pitch_end - self.pitch) * progress
+                + self.vibrato_depth * (TAU * self.vibrato * t).sin();
+            phase += freq.max(0.) / SAMPLE_RATE as f32;
+            if 1. <= phase {
+                phase = phase.fract();
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                noise = (seed >> 16) as f32 / 32768. - 1.;
+            }
+            let value = match self.wave {
+                Wave::Square if phase < 0.5 => 1.,
+                Wave::Square => -1.,
+                Wave::Triangle => 4. * (phase - 0.5).abs() - 1.,
+                Wave::Noise => noise,
+            };
+            let value = value * self.envelope(t * 1000.) * self.volume.clamp(0., 1.);
+            samples.push((value * i16::MAX as f32) as i16);
+        }
+        samples
+    }
+    // 経過時間[ミリ秒]での音量の倍率(0〜1)
+    fn envelope(&self, ms: f32) -> f32 {
+        let attack = if 0. < self.attack {
+            (ms / self.attack).min(1.)
+        } else {
+            1.
+        };
+        let release = if 0. < self.release {
+            ((self.length - ms) / self.release).clamp(0., 1.)
+        } else {
+            1.
+        };
+        attack * release
+    }
+    fn set(&mut self, param: &str, value: &str) -> Result<(), String> {
+        if param == "wave" {
+            self.wave = Wave::from_name(value)
+                .ok_or_else(|| format!("{}という波形はありません。", value))?;
+            return Ok(());
+        }
+        let value: f32 = value
+            .parse()
+            .ok()
+            .filter(|v: &f32| v.is_finite())
+            .ok_or_else(|| format!("{}は数値で指定してください。", param))?;
+        // 大きすぎる値で音声データが巨大にならないよう、項目ごとの上限に収める
+        let (target, max) = match param {
+            "pitch" => (&mut self.pitch, MAX_PITCH),
+            "pitch_end" => (&mut self.pitch_end, MAX_PITCH),
+            "length" => (&mut self.length, MAX_LENGTH),
+            "attack" => (&mut self.attack, MAX_LENGTH),
+            "release" => (&mut self.release, MAX_LENGTH),
+            "vibrato" => (&mut self.vibrato, MAX_PITCH),
+            "vibrato_depth" => (&mut self.vibrato_depth, MAX_PITCH),
+            "volume" => (&mut self.volume, 1.),
+            _ => return Err(format!("{}という項目はありません。", param)),
+        };
+        *target = value.clamp(0., max);
+        Ok(())
+    }
+}
+
+// 効果音ごとの音色(効果音の名前はaudio/にあるWAVファイルの名前と同じ)
+pub struct SynthConfig {
+    voices: Vec<(&'static str, Voice)>,
+}
+
+impl Default for SynthConfig {
+    fn default() -> Self {
+        // 侵攻音は下がっていく4音
+        let step = |pitch| Voice {
+            attack: 5.,
+            volume: 0.8,
+            ..Voice::new(Wave::Square, pitch, pitch, 90., 60.)
+        };
+        SynthConfig {
+            voices: vec![
+                ("shoot", Voice::new(Wave::Square, 1600., 200., 200., 150.)),
+                (
+                    "player_explosion",
+                    Voice {
+                        volume: 0.6,
+                        ..Voice::new(Wave::Noise, 4000., 500., 1000., 900.)
+                    },
+                ),
+                ("fastinvader1", step(110.)),
+                ("fastinvader2", step(98.)),
+                ("fastinvader3", step(87.)),
+                ("fastinvader4", step(82.)),
+                (
+                    "invader_killed",
+                    Voice::new(Wave::Square, 900., 150., 250., 200.),
+                ),
+                // ループ再生するので、途切れ目で音量と揺れが揃うようにする
+                (
+                    "ufo_flying",
+                    Voice {
+                        vibrato: 8.,
+                        vibrato_depth: 200.,
+                        volume: 1.,
+                        ..Voice::new(Wave::Triangle, 700., 700., 1000., 0.)
+                    },
+                ),
+                (
+                    "ufo_explosion",
+                    Voice {
+                        vibrato: 16.,
+                        vibrato_depth: 150.,
+                        volume: 1.,
+                        ..Voice::new(Wave::Square, 300., 300., 1000., 800.)
+                    },
+                ),
+            ],
+        }
+    }
+}
+
+impl SynthConfig {
+    // ファイルが無い場合は既定の音色を返す
+    pub fn load(path: &str) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(SynthConfig::default()),
+            Err(e) => return Err(e),
+        };
+        let mut config = SynthConfig::default();
+        // 1行に「効果音名 項目名 値」
+        // 空行と//で始まる行は無視し、書かれていない項目は既定の値のまま
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            config.parse_line(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}行目が不正です: {}", i + 1, e),
+                )
+            })?;
+        }
+        Ok(config)
+    }
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+        let (Some(name), Some(param), Some(value), None) =
+            (words.next(), words.next(), words.next(), words.next())
+        else {
+            return Err("「効果音名 項目名 値」の形式で書いてください。".to_owned());
+        };
+        let voice = self
+            .voices
+            .iter_mut()
+            .find(|(n, _)| *n == name)
+            .map(|(_, voice)| voice)
+            .ok_or_else(|| format!("{}という効果音はありません。", name))?;
+        voice.set(param, value)
+    }
+    // 指定した効果音をWAV形式で生成する
+    pub fn wav(&self, name: &str) -> Option<Vec<u8>> {
+        let (_, voice) = self.voices.iter().find(|(n, _)| *n == name)?;
+        Some(encode_wav(&voice.samples()))
+    }
+}
+
+// モノラル16ビットのWAV形式にする
+fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = 2 * samples.len() as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmtチャンクの大きさ
+    wav.extend_from_slice(&1u16.to_le_bytes()); // リニアPCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // チャンネル数
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(2 * SAMPLE_RATE).to_le_bytes()); // 1秒あたりのバイト数
+    wav.extend_from_slice(&2u16.to_le_bytes()); // 1サンプルのバイト数
+    wav.extend_from_slice(&16u16.to_le_bytes()); // 1サンプルのビット数
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], i: usize) -> u16 {
+        u16::from_le_bytes([bytes[i], bytes[i + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], i: usize) -> u32 {
+        u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+    }
+
+    #[test]
+    fn wav_header_describes_the_samples() {
+        let wav = encode_wav(&[0, 1, -1]);
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32_at(&wav, 4), 36 + 6);
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(&wav, 16), 16);
+        assert_eq!(u16_at(&wav, 20), 1);
+        assert_eq!(u16_at(&wav, 22), 1);
+        assert_eq!(u32_at(&wav, 24), SAMPLE_RATE);
+        assert_eq!(u32_at(&wav, 28), 2 * SAMPLE_RATE);
+        assert_eq!(u16_at(&wav, 32), 2);
+        assert_eq!(u16_at(&wav, 34), 16);
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(u32_at(&wav, 40), 6);
+        assert_eq!(&wav[44..], [0, 0, 1, 0, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn rejects_broken_lines() {
+        let mut config = SynthConfig::default();
+        for line in [
+            "shoot length",
+            "shoot length 100 200",
+            "laser length 100",
+            "shoot speed 100",
+            "shoot length long",
+            "shoot length NaN",
+            "shoot wave sine",
+        ] {
+            assert!(config.parse_line(line).is_err(), "{}", line);
+        }
+        assert!(config.parse_line("shoot wave noise").is_ok());
+    }
+
+    #[test]
+    fn samples_match_the_length() {
+        let voice = Voice::new(Wave::Square, 440., 440., 100., 0.);
+        assert_eq!(voice.samples().len(), 4410);
+        // 長すぎる指定は上限に収める
+        let mut config = SynthConfig::default();
+        config.parse_line("shoot length 1e30").unwrap();
+        config.parse_line("shoot pitch 1e7").unwrap();
+        let (_, voice) = config.voices.iter().find(|(n, _)| *n == "shoot").unwrap();
+        assert_eq!(voice.length, MAX_LENGTH);
+        assert_eq!(voice.pitch, MAX_PITCH);
+        assert_eq!(
+            voice.samples().len(),
+            (MAX_LENGTH / 1000. * SAMPLE_RATE as f32) as usize
+        );
+    }
+}
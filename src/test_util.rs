@@ -0,0 +1,5 @@
+// テスト用の一時ファイルのパス(同時に動く別のテストのプロセスと重ならないようプロセスIDを付ける)
+pub fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+    path.to_string_lossy().into_owned()
+}
@@ -0,0 +1,28 @@
+// ゲームの更新間隔[秒](60Hz固定)
+pub const TICK: f64 = 1. / 60.;
+// 処理落ちしたときに1回の描画の間で追いつく更新回数の上限
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+// 描画のフレームレートによらず一定間隔でゲームを更新するための時間管理
+#[derive(Default)]
+pub struct FixedTimestep {
+    accumulator: f64, // まだ更新に使われていない経過時間[秒]
+}
+
+impl FixedTimestep {
+    // 前回の描画からの経過時間を加え、今回の描画までに行う更新回数を返す
+    pub fn advance(&mut self, frame_time: f64) -> u32 {
+        self.accumulator += frame_time;
+        let mut ticks = 0;
+        while TICK <= self.accumulator {
+            self.accumulator -= TICK;
+            ticks += 1;
+            if MAX_TICKS_PER_FRAME <= ticks {
+                // 追いつけない分は捨てる
+                self.accumulator = 0.;
+                break;
+            }
+        }
+        ticks
+    }
+}
@@ -1,72 +1,53 @@
-use crate::array_sprite::array_sprite;
+use crate::array_sprite::{array_sprite, array_string};
 use crate::canvas;
-use crate::dot_map::Color;
-use crate::dot_map::*;
+use crate::dot_map::{ColorRule, DotMap};
 
 use macroquad::prelude::*;
-use std::io::Write;
 
 pub struct TopArea {
-    top: Vec<Vec<u8>>,
-    num_sprite: Vec<Vec<u8>>,
+    top: DotMap,
+    font: Vec<Vec<u8>>, // 0〜9、A〜Z、記号のスプライト
 }
 
 impl TopArea {
-    pub fn new(num_sprite: Vec<Vec<u8>>) -> Self {
+    pub fn new(font: Vec<Vec<u8>>) -> Self {
         // 0クリアしたドットマップを生成
         TopArea {
-            top: vec![vec![0; canvas::TOP_WIDTH as usize]; (canvas::TOP_HEIGHT / 8) as usize],
-            num_sprite,
+            top: DotMap::new(canvas::TOP_WIDTH, canvas::TOP_HEIGHT, ColorRule::Top),
+            font,
         }
     }
     // すべて消す
     pub fn all_clear(&mut self) {
-        self.top = vec![vec![0; canvas::TOP_WIDTH as usize]; (canvas::TOP_HEIGHT / 8) as usize];
+        self.top.all_clear();
     }
-    // 上に獲得得点を表示
-    pub fn draw_score(&mut self, mut score: i32) {
-        let mut score_num = Vec::new();
+    // 上に各プレイヤーの獲得得点とハイスコアを見出し付きで表示
+    pub fn draw_score(&mut self, score: [i32; 2], hi_score: i32) {
+        array_string(&mut self.top.map, IVec2::new(8, 8), "SCORE<1>", &self.font);
+        array_string(&mut self.top.map, IVec2::new(80, 8), "HI-SCORE", &self.font);
+        array_string(
+            &mut self.top.map,
+            IVec2::new(152, 8),
+            "SCORE<2>",
+            &self.font,
+        );
+        self.draw_num(IVec2::new(24, 24), score[0]);
+        self.draw_num(IVec2::new(88, 24), hi_score);
+        self.draw_num(IVec2::new(160, 24), score[1]);
+    }
+    // 指定位置から5桁の数字を表示
+    fn draw_num(&mut self, mut pos: IVec2, mut num: i32) {
+        let mut digits = Vec::new();
         for _ in 0..5 {
-            score_num.push(score % 10);
-            score /= 10;
+            digits.push(num % 10);
+            num /= 10;
         }
-        let mut pos = IVec2::new(24, 24);
         for i in (0..5).rev() {
-            array_sprite(&mut self.top, pos, &self.num_sprite[score_num[i] as usize]);
+            array_sprite(&mut self.top.map, pos, &self.font[digits[i] as usize]);
             pos.x += 8;
         }
     }
-    pub fn dot_map2texture(&self, player_exploding: bool) -> Texture2D {
-        let rgba = self.convert_to_color_bytes(player_exploding);
-        Self::rgba2texture(rgba)
-    }
-
-    // DotMapを1ピクセル4バイトでrgbaを表し、u8のベクタにまとめる
-    fn convert_to_color_bytes(&self, player_exploding: bool) -> Vec<u8> {
-        let mut color_bytes: Vec<u8> = Vec::new();
-        for i_char in 0..(canvas::TOP_HEIGHT / 8) as usize {
-            for bit in 0..8 {
-                for pos_x in 0..canvas::TOP_WIDTH as usize {
-                    if self.top[i_char][pos_x] & (1 << bit) == 0 {
-                        color_bytes.write(&[0, 0, 0, 255]).unwrap();
-                    } else {
-                        if player_exploding {
-                            // プレイヤーが爆発中はすべて赤にする
-                            color_bytes.write(&set_color(Color::Red)).unwrap();
-                        } else {
-                            color_bytes.write(&set_color(Color::White)).unwrap();
-                        }
-                    }
-                }
-            }
-        }
-        color_bytes
-    }
-    // RGBAデータをテクスチャデータに変換
-    fn rgba2texture(rgba: Vec<u8>) -> Texture2D {
-        let texture =
-            Texture2D::from_rgba8(canvas::TOP_WIDTH as u16, canvas::TOP_HEIGHT as u16, &rgba);
-        texture.set_filter(FilterMode::Nearest);
-        texture
+    pub fn plane(&self) -> &DotMap {
+        &self.top
     }
 }
@@ -1,8 +1,12 @@
 use crate::canvas;
+use crate::sound::{Se, SoundQueue};
 use crate::{array_sprite::ArraySprite, dot_map::DotMap};
-use macroquad::audio::*;
 use macroquad::prelude::*;
-use macroquad::time;
+
+// UFOが消滅してから次に出現できるまでのカウント数(60fpsで25秒)
+pub const SPAWN_INTERVAL_CNT: i32 = 25 * 60;
+// UFOが出現できるエイリアンの残り数の最低値
+pub const SPAWN_MIN_ALIEN_NUM: i32 = 8;
 
 pub struct Explosion {
     pos: IVec2,
@@ -37,33 +41,18 @@ impl Explosion {
         }
     }
     fn draw_score(&self, dot_map: &mut DotMap) {
-        let i_sprite: Vec<usize>;
-        match self.score {
-            50 => {
-                i_sprite = vec![5, 0];
-            }
-            100 => {
-                i_sprite = vec![1, 0, 0];
-            }
-            150 => {
-                i_sprite = vec![1, 5, 0];
-            }
-            300 => {
-                i_sprite = vec![3, 0, 0];
-            }
-            _ => panic!("UFOの点数が不正です。"),
-        }
         let mut pos = self.pos;
-        for i in 0..i_sprite.len() {
-            Self::array_sprite_num(dot_map, &self.sprite_num[i_sprite[i]], pos);
+        for c in self.score.to_string().chars() {
+            let i = c.to_digit(10).unwrap_or(0) as usize;
+            Self::array_sprite_num(dot_map, &self.sprite_num[i], pos);
             pos.x += 8;
         }
     }
     // バイト境界をまたがない物体の描画を透過なしで行う(上書き)
-    fn array_sprite_num(dot_map: &mut DotMap, sprite: &Vec<u8>, pos: IVec2) {
+    fn array_sprite_num(dot_map: &mut DotMap, sprite: &[u8], pos: IVec2) {
         let char_y = (pos.y / 8) as usize;
-        for dx in 0..sprite.len() {
-            dot_map.map[char_y][pos.x as usize + dx] = sprite[dx];
+        for (dx, &byte) in sprite.iter().enumerate() {
+            dot_map.map[char_y][pos.x as usize + dx] = byte;
         }
     }
 }
@@ -82,23 +71,16 @@ pub struct Ufo {
     pre_pos: IVec2,         // 前回描画時の位置
     live: bool,             // 存在しているか否か
     move_dir: i32,          // 移動方向
-    lapse_time: f64,        // 前回画面から消滅したときの時刻
+    lapse_cnt: i32,         // 前回画面から消滅してからの経過カウント
     score_table: [i32; 15], // プレイヤーの発射数に対応した獲得得点表
     sprite: Vec<u8>,        // 左側から縦8ピクセルずつを8bitのベクタで表す
     pub explosion: Explosion,
-    se_flying: Sound,
-    se_explosion: Sound,
-    se_volume: f32, // 発射音の音量(0〜1)
+    pub commanded: bool, // 真なら対戦プレイの2人目が発進させたときだけ出現する
+    launch: bool,        // 2人目がこのフレームで発進させたら真
 }
 
 impl Ufo {
-    pub fn new(
-        sprite: Vec<u8>,
-        explosion_sprite: Vec<u8>,
-        se_flying: Sound,
-        se_explosion: Sound,
-        num_list: Vec<Vec<u8>>,
-    ) -> Self {
+    pub fn new(sprite: Vec<u8>, explosion_sprite: Vec<u8>, num_list: Vec<Vec<u8>>) -> Self {
         Ufo {
             width: sprite.len() as i32,
             pos: IVec2::new(0, 8),
@@ -108,7 +90,7 @@ impl Ufo {
             score_table: [
                 50, 50, 100, 150, 100, 100, 50, 300, 100, 100, 100, 50, 150, 100, 100,
             ],
-            lapse_time: time::get_time(),
+            lapse_cnt: 0,
             sprite,
             explosion: Explosion {
                 pos: IVec2::new(0, 0),
@@ -117,71 +99,84 @@ impl Ufo {
                 sprite_num: num_list,
                 score: 0,
             },
-            se_flying,
-            se_explosion,
-            se_volume: 0.2,
+            commanded: false,
+            launch: false,
         }
     }
-    pub fn set_se_volume(&mut self, volume: i32) {
-        // UFOの音源が大きいので少し下げる
-        self.se_volume = (volume as f32) / 100. * 0.3;
+    // 画面上にいる場合は左上位置を返す
+    pub fn pos(&self) -> Option<IVec2> {
+        self.live.then_some(self.pos)
+    }
+    // 消滅してから一定時間経過して、かつエイリアンの数が一定以上なら真
+    pub fn can_launch(&self, alien_num: i32) -> bool {
+        !self.live && SPAWN_INTERVAL_CNT < self.lapse_cnt && SPAWN_MIN_ALIEN_NUM <= alien_num
     }
-    pub fn reset(&mut self) {
+    // 次のフレームで出現させる(出現できなければ何もしない)
+    pub fn launch(&mut self) {
+        self.launch = true;
+    }
+    pub fn reset(&mut self, se: &mut SoundQueue) {
         self.live = false;
-        self.lapse_time = time::get_time();
-        stop_sound(self.se_flying);
-        stop_sound(self.se_explosion);
+        self.lapse_cnt = 0;
+        self.launch = false;
+        se.stop(Se::UfoFlying);
+        se.stop(Se::UfoExplosion);
     }
-    fn remove(&mut self, dot_map: &mut DotMap) {
+    fn remove(&mut self, dot_map: &mut DotMap, se: &mut SoundQueue) {
         self.live = false;
         // 移動方向反転
         self.move_dir *= -1;
         // タイマーリセット
-        self.lapse_time = time::get_time();
+        self.lapse_cnt = 0;
 
         // 前回描画した部分を消す
         self.erase(dot_map, self.pre_pos);
         // 飛行音を止める
-        stop_sound(self.se_flying);
+        se.stop(Se::UfoFlying);
     }
     // プレイヤーの弾が当たった場合
-    pub fn hit_player_bullet(&mut self, dot_map: &mut DotMap, fire_cnt: i32) -> i32 {
+    pub fn hit_player_bullet(
+        &mut self,
+        dot_map: &mut DotMap,
+        fire_cnt: i32,
+        se: &mut SoundQueue,
+    ) -> i32 {
         // UFOを消す
-        self.remove(dot_map);
+        self.remove(dot_map, se);
         // 爆発エフェクト描画
         self.explosion.create_effect(dot_map, self.pos);
 
         // 爆発音再生
-        // wasmでは再生しない
-        #[cfg(not(target_arch = "wasm32"))]
-        play_sound(
-            self.se_explosion,
-            PlaySoundParams {
-                looped: false,
-                volume: self.se_volume,
-            },
-        );
+        se.play(Se::UfoExplosion);
 
         let score = self.score_table[(fire_cnt - 1) as usize % 15];
         self.explosion.score = score;
         score
     }
-    pub fn update(&mut self, dot_map: &mut DotMap, fire_cnt: i32, alien_num: i32) {
+    pub fn update(
+        &mut self,
+        dot_map: &mut DotMap,
+        fire_cnt: i32,
+        alien_num: i32,
+        se: &mut SoundQueue,
+    ) {
         self.pre_pos = self.pos;
         self.explosion.update_draw(dot_map);
+        let launch = std::mem::take(&mut self.launch);
         // 画面の反対側まで到達した場合
         if (self.move_dir < 0 && self.pos.x < 8)
             || (0 < self.move_dir && canvas::GAME_WIDTH - 8 <= self.pos.x + self.width)
         {
-            self.remove(dot_map);
+            self.remove(dot_map, se);
             return;
         }
         // 移動中
         if self.live {
             self.pos.x += self.move_dir;
         } else {
-            // 消滅してから一定時間経過して、かつエイリアンの数が8以上だったら
-            if 25. < time::get_time() - self.lapse_time && 7 < alien_num {
+            self.lapse_cnt += 1;
+            // 出現できる状態で、2人目の指示を受ける場合は発進させられたら
+            if self.can_launch(alien_num) && (!self.commanded || launch) {
                 // UFOが出現する瞬間
                 self.live = true;
                 // プレイヤーの発射数が偶数であれば右から左へ動く
@@ -195,15 +190,7 @@ impl Ufo {
                 }
 
                 // 飛行音再生開始
-                // wasmでは再生しない
-                #[cfg(not(target_arch = "wasm32"))]
-                play_sound(
-                    self.se_flying,
-                    PlaySoundParams {
-                        looped: true,
-                        volume: self.se_volume,
-                    },
-                );
+                se.play_loop(Se::UfoFlying);
             }
         }
         self.draw(dot_map);